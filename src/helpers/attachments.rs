@@ -0,0 +1,256 @@
+use crate::helpers::utils::{AppData, Attachment, generate_id, now_timestamp};
+use crate::helpers::vault::{VaultError, get_data_file_path, open_backups, write_atomic};
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+// Il file viene cifrato a blocchi: ogni blocco è nonce (12 byte) + testo cifrato con tag (16 byte)
+const CHUNK_SIZE: usize = 64 * 1024;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+// Cartella accanto a data.json, un file .bin per allegato
+pub fn attachments_dir() -> PathBuf {
+    get_data_file_path().with_file_name("attachments")
+}
+
+fn attachment_path(id: &str) -> PathBuf {
+    attachments_dir().join(format!("{}.bin", id))
+}
+
+// Ogni blocco è legato ad allegato, posizione e fine file: non si possono scambiare,
+// riordinare o troncare senza che la decifratura fallisca
+fn chunk_aad(vault_id: &str, entry_id: &str, attachment_id: &str, index: usize, last: bool) -> Vec<u8> {
+    format!("{}/{}/{}/{}/{}", vault_id, entry_id, attachment_id, index, u8::from(last)).into_bytes()
+}
+
+pub fn store_attachment(
+    source: &Path,
+    limit: u64,
+    key: &[u8; 32],
+    vault_id: &str,
+    entry_id: &str,
+) -> Result<Attachment, VaultError> {
+    let size = fs::metadata(source).map_err(|e| VaultError::io(source, e))?.len();
+    if size > limit {
+        return Err(VaultError::TooLarge { size, limit });
+    }
+
+    let plaintext = Zeroizing::new(fs::read(source).map_err(|e| VaultError::io(source, e))?);
+    let id = generate_id();
+    let sealed = seal_chunks(&plaintext, key, &|index, last| chunk_aad(vault_id, entry_id, &id, index, last))?;
+
+    let dir = attachments_dir();
+    fs::create_dir_all(&dir).map_err(|e| VaultError::io(&dir, e))?;
+    let path = attachment_path(&id);
+    write_atomic(&path, &sealed).map_err(|e| VaultError::io(&path, e))?;
+
+    let name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "allegato".to_string());
+
+    Ok(Attachment {
+        id,
+        name,
+        size: plaintext.len() as u64,
+        added_at: now_timestamp(),
+    })
+}
+
+pub fn load_attachment(
+    attachment: &Attachment,
+    key: &[u8; 32],
+    vault_id: &str,
+    entry_id: &str,
+) -> Result<Zeroizing<Vec<u8>>, VaultError> {
+    let path = attachment_path(&attachment.id);
+    let sealed = fs::read(&path).map_err(|e| VaultError::io(&path, e))?;
+    let plaintext = open_chunks(&sealed, key, &|index, last| {
+        chunk_aad(vault_id, entry_id, &attachment.id, index, last)
+    })?;
+
+    if plaintext.len() as u64 != attachment.size {
+        return Err(VaultError::Crypto("dimensione dell'allegato non valida".to_string()));
+    }
+    Ok(plaintext)
+}
+
+pub fn delete_attachment_file(id: &str) -> Result<(), VaultError> {
+    let path = attachment_path(id);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(VaultError::io(&path, e)),
+        _ => Ok(()),
+    }
+}
+
+fn attachment_ids(data: &AppData) -> impl Iterator<Item = &str> {
+    data.ps
+        .iter()
+        .chain(data.trash.iter().map(|trashed| &trashed.entry))
+        .flat_map(|entry| entry.attachments.iter().map(|attachment| attachment.id.as_str()))
+}
+
+// Elimina i file che nessuna entry (nemmeno nel cestino) usa più, né nel vault né nei backup:
+// ripristinando un backup i suoi allegati devono esserci ancora. Se un backup non si apre non
+// si elimina niente. Restituisce quanti file ha eliminato
+pub fn remove_orphan_attachments(data: &AppData, key: &[u8; 32]) -> usize {
+    let Ok(files) = fs::read_dir(attachments_dir()) else {
+        return 0;
+    };
+    let Ok(backups) = open_backups(key) else {
+        return 0;
+    };
+
+    let in_use: Vec<&str> = attachment_ids(data).chain(backups.iter().flat_map(attachment_ids)).collect();
+
+    let mut removed = 0;
+    for path in files.flatten().map(|file| file.path()) {
+        let is_attachment = path.extension().is_some_and(|extension| extension == "bin");
+        let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if is_attachment && !in_use.contains(&id) && fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+fn seal_chunks(
+    plaintext: &[u8],
+    key: &[u8; 32],
+    aad: &dyn Fn(usize, bool) -> Vec<u8>,
+) -> Result<Vec<u8>, VaultError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let chunk_count = plaintext.len().div_ceil(CHUNK_SIZE).max(1);
+    let mut sealed = Vec::with_capacity(plaintext.len() + chunk_count * (NONCE_SIZE + TAG_SIZE));
+
+    // Anche un file vuoto ha un blocco, così la fine del file è sempre autenticata
+    for index in 0..chunk_count {
+        let start = index * CHUNK_SIZE;
+        let end = (start + CHUNK_SIZE).min(plaintext.len());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext[start..end],
+                    aad: &aad(index, index + 1 == chunk_count),
+                },
+            )
+            .map_err(|e| VaultError::Crypto(format!("{:?}", e)))?;
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+    }
+    Ok(sealed)
+}
+
+fn open_chunks(
+    sealed: &[u8],
+    key: &[u8; 32],
+    aad: &dyn Fn(usize, bool) -> Vec<u8>,
+) -> Result<Zeroizing<Vec<u8>>, VaultError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(sealed.len()));
+    let mut rest = sealed;
+    let mut index = 0;
+
+    loop {
+        if rest.len() < NONCE_SIZE + TAG_SIZE {
+            return Err(VaultError::Crypto("allegato troncato".to_string()));
+        }
+        let (nonce, after_nonce) = rest.split_at(NONCE_SIZE);
+        let length = after_nonce.len().min(CHUNK_SIZE + TAG_SIZE);
+        let (ciphertext, after_chunk) = after_nonce.split_at(length);
+        let last = after_chunk.is_empty();
+
+        let chunk = cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad(index, last),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| VaultError::Crypto("allegato danneggiato o manomesso".to_string()))?;
+        plaintext.extend_from_slice(&chunk);
+
+        if last {
+            return Ok(plaintext);
+        }
+        rest = after_chunk;
+        index += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEALED_CHUNK: usize = NONCE_SIZE + CHUNK_SIZE + TAG_SIZE;
+
+    fn aad(index: usize, last: bool) -> Vec<u8> {
+        chunk_aad("vault", "entry", "allegato", index, last)
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = [7u8; 32];
+        for len in [0, 1, CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE + 100] {
+            let plaintext = sample(len);
+            let sealed = seal_chunks(&plaintext, &key, &aad).unwrap();
+            let chunks = len.div_ceil(CHUNK_SIZE).max(1);
+            assert_eq!(sealed.len(), len + chunks * (NONCE_SIZE + TAG_SIZE), "{} byte", len);
+            assert_eq!(*open_chunks(&sealed, &key, &aad).unwrap(), plaintext, "{} byte", len);
+        }
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let key = [7u8; 32];
+        let sealed = seal_chunks(&sample(2 * CHUNK_SIZE + 10), &key, &aad).unwrap();
+
+        let mut tampered = sealed.clone();
+        tampered[SEALED_CHUNK + NONCE_SIZE + 5] ^= 1;
+        assert!(open_chunks(&tampered, &key, &aad).is_err());
+
+        // Chiave o dati associati di un altro allegato
+        assert!(open_chunks(&sealed, &[8u8; 32], &aad).is_err());
+        let other = |index, last| chunk_aad("vault", "entry", "altro", index, last);
+        assert!(open_chunks(&sealed, &key, &other).is_err());
+    }
+
+    #[test]
+    fn swapped_chunks_are_detected() {
+        let key = [7u8; 32];
+        let sealed = seal_chunks(&sample(3 * CHUNK_SIZE), &key, &aad).unwrap();
+
+        let mut swapped = sealed[SEALED_CHUNK..2 * SEALED_CHUNK].to_vec();
+        swapped.extend_from_slice(&sealed[..SEALED_CHUNK]);
+        swapped.extend_from_slice(&sealed[2 * SEALED_CHUNK..]);
+        assert!(open_chunks(&swapped, &key, &aad).is_err());
+    }
+
+    #[test]
+    fn truncation_is_detected() {
+        let key = [7u8; 32];
+        let sealed = seal_chunks(&sample(2 * CHUNK_SIZE + 10), &key, &aad).unwrap();
+
+        // Senza l'ultimo blocco quello prima non è marcato come finale
+        assert!(open_chunks(&sealed[..2 * SEALED_CHUNK], &key, &aad).is_err());
+        assert!(open_chunks(&sealed[..SEALED_CHUNK], &key, &aad).is_err());
+        assert!(open_chunks(&sealed[..sealed.len() - 1], &key, &aad).is_err());
+        assert!(open_chunks(&sealed[..NONCE_SIZE + TAG_SIZE - 1], &key, &aad).is_err());
+        assert!(open_chunks(&[], &key, &aad).is_err());
+    }
+}
@@ -1,21 +1,366 @@
-use rand::{Rng, distr::Alphanumeric};
+use crate::helpers::utils::SecretString;
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use zeroize::Zeroizing;
 
-pub fn generate_password() -> String {
-    const SYMBOLS: &[u8] = b"!@#$%^&*()-_=+[]{};:,.<>?";
+pub const MIN_LENGTH: usize = 4;
+pub const MAX_LENGTH: usize = 128;
+pub const DEFAULT_SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>?";
+const AMBIGUOUS: &str = "0O1lI";
+
+pub const MIN_WORDS: usize = 3;
+pub const MAX_WORDS: usize = 20;
+
+// Elenco lungo EFF (https://www.eff.org/dice): una parola per ogni combinazione di 5 dadi.
+// Electronic Frontier Foundation, distribuito con licenza CC BY 3.0 US
+// (https://creativecommons.org/licenses/by/3.0/us/)
+const EFF_WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+const EFF_WORD_COUNT: usize = 7776;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorMode {
+    #[default]
+    Password,
+    Passphrase, // Parole dall'elenco EFF, più facili da digitare a mano
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub add_digit: bool,  // Una cifra in fondo a una parola a caso
+    pub add_symbol: bool, // Un simbolo dell'insieme scelto in fondo a una parola a caso
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            add_digit: false,
+            add_symbol: false,
+        }
+    }
+}
+
+// Impostazioni del generatore, modificabili dal popup dei pannelli di aggiunta e modifica
+// e salvabili nel vault come profili
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorOptions {
+    pub mode: GeneratorMode,
+    pub passphrase: PassphraseOptions,
+    pub length: usize,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub symbol_set: String,
+    pub exclude_ambiguous: bool,
+    pub min_per_class: usize, // Caratteri minimi per ogni classe attiva
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::default(),
+            passphrase: PassphraseOptions::default(),
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            digits: true,
+            symbols: true,
+            symbol_set: DEFAULT_SYMBOLS.to_string(),
+            exclude_ambiguous: false,
+            min_per_class: 1,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    // Caratteri di ogni classe attiva, già senza quelli ambigui se richiesto
+    fn classes(&self) -> Vec<Vec<char>> {
+        let symbols: String = self.symbol_set.chars().filter(|c| !c.is_whitespace()).collect();
+        let classes = [
+            (self.uppercase, "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+            (self.lowercase, "abcdefghijklmnopqrstuvwxyz"),
+            (self.digits, "0123456789"),
+            (self.symbols, symbols.as_str()),
+        ];
+
+        classes
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, chars)| {
+                let mut class: Vec<char> = chars
+                    .chars()
+                    .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS.contains(*c))
+                    .collect();
+                // Un simbolo ripetuto nell'insieme non deve uscire più spesso degli altri
+                class.sort_unstable();
+                class.dedup();
+                class
+            })
+            .collect()
+    }
+
+    // Descrizione breve per l'elenco dei profili
+    pub fn summary(&self) -> String {
+        if self.mode == GeneratorMode::Passphrase {
+            return format!("{} parole", self.passphrase.words);
+        }
+        let classes: Vec<&str> = [
+            (self.uppercase, "A-Z"),
+            (self.lowercase, "a-z"),
+            (self.digits, "0-9"),
+            (self.symbols, self.symbol_set.as_str()),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, label)| label)
+        .collect();
+        format!("{} caratteri • {}", self.length, classes.join(" "))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == GeneratorMode::Passphrase {
+            return self.validate_passphrase();
+        }
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&self.length) {
+            return Err(format!("La lunghezza deve essere tra {} e {}", MIN_LENGTH, MAX_LENGTH));
+        }
+        let classes = self.classes();
+        if classes.is_empty() {
+            return Err("Seleziona almeno un tipo di carattere".to_string());
+        }
+        if classes.iter().any(Vec::is_empty) {
+            return Err("L'insieme di simboli è vuoto".to_string());
+        }
+        if self.min_per_class * classes.len() > self.length {
+            return Err(format!(
+                "{} caratteri minimi per {} tipi superano la lunghezza di {}",
+                self.min_per_class,
+                classes.len(),
+                self.length
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_passphrase(&self) -> Result<(), String> {
+        let passphrase = &self.passphrase;
+        if !(MIN_WORDS..=MAX_WORDS).contains(&passphrase.words) {
+            return Err(format!("Il numero di parole deve essere tra {} e {}", MIN_WORDS, MAX_WORDS));
+        }
+        if passphrase.add_symbol && !self.symbol_set.chars().any(|c| !c.is_whitespace()) {
+            return Err("L'insieme di simboli è vuoto".to_string());
+        }
+        eff_words().map(|_| ())
+    }
+}
+
+// Le righe sono "11111<tab>parola"; l'elenco viene letto e controllato una sola volta
+fn eff_words() -> Result<&'static [&'static str], String> {
+    static WORDS: OnceLock<Result<Vec<&'static str>, String>> = OnceLock::new();
+    let words = WORDS.get_or_init(|| {
+        let words: Vec<&str> = EFF_WORDLIST
+            .lines()
+            .filter_map(|line| line.split_whitespace().last())
+            .collect();
+
+        // Con un elenco incompleto la passphrase sarebbe più debole di quanto promesso
+        let mut unique = words.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        if words.len() != EFF_WORD_COUNT || unique.len() != EFF_WORD_COUNT {
+            return Err(format!(
+                "Elenco di parole EFF non valido ({} parole invece di {})",
+                unique.len(),
+                EFF_WORD_COUNT
+            ));
+        }
+        Ok(words)
+    });
+    words.as_deref().map_err(String::clone)
+}
+
+fn generate_passphrase(options: &GeneratorOptions) -> Result<SecretString, String> {
+    let passphrase = &options.passphrase;
+    let list = eff_words()?;
     let mut rng = rand::rng();
-    let length: usize = 12;
 
-    let password: Vec<u8> = (0..length)
+    let mut words: Vec<Zeroizing<String>> = (0..passphrase.words)
         .map(|_| {
-            let choice = rng.random_range(0..4);
-            match choice {
-                0 => rng.sample(Alphanumeric) as u8,
-                1 => rng.sample(Alphanumeric) as u8,
-                2 => SYMBOLS[rng.random_range(0..SYMBOLS.len())],
-                _ => rng.random_range(b'A'..=b'Z'),
+            let word = list[rng.random_range(0..list.len())];
+            let mut chars = word.chars();
+            let mut word = Zeroizing::new(String::with_capacity(word.len() + 8));
+            if passphrase.capitalize && let Some(first) = chars.next() {
+                word.extend(first.to_uppercase());
             }
+            word.extend(chars);
+            word
         })
         .collect();
 
-    String::from_utf8_lossy(&password).to_string()
+    if passphrase.add_digit {
+        let index = rng.random_range(0..words.len());
+        words[index].push(char::from(b'0' + rng.random_range(0..10u8)));
+    }
+    if passphrase.add_symbol {
+        let symbols: Vec<char> = options.symbol_set.chars().filter(|c| !c.is_whitespace()).collect();
+        let index = rng.random_range(0..words.len());
+        words[index].push(symbols[rng.random_range(0..symbols.len())]);
+    }
+
+    let length = words.iter().map(|word| word.len()).sum::<usize>() + passphrase.separator.len() * words.len();
+    let mut password = Zeroizing::new(String::with_capacity(length));
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            password.push_str(&passphrase.separator);
+        }
+        password.push_str(word);
+    }
+    Ok(password)
+}
+
+pub fn generate_password(options: &GeneratorOptions) -> Result<SecretString, String> {
+    options.validate()?;
+    if options.mode == GeneratorMode::Passphrase {
+        return generate_passphrase(options);
+    }
+    let classes = options.classes();
+    let mut rng = rand::rng();
+
+    // Prima i minimi di ogni classe, poi il resto da tutte le classi insieme
+    let mut chars = Zeroizing::new(Vec::with_capacity(options.length));
+    for class in &classes {
+        for _ in 0..options.min_per_class {
+            chars.push(class[rng.random_range(0..class.len())]);
+        }
+    }
+    let pool: Vec<char> = classes.concat();
+    while chars.len() < options.length {
+        chars.push(pool[rng.random_range(0..pool.len())]);
+    }
+    // Mescola, altrimenti i caratteri obbligatori starebbero sempre all'inizio
+    chars.shuffle(&mut rng);
+
+    // Capacità esatta: i simboli personalizzati possono occupare più byte
+    let mut password = Zeroizing::new(String::with_capacity(chars.iter().map(|c| c.len_utf8()).sum()));
+    password.extend(chars.iter());
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_follow_class_rules() {
+        let options = GeneratorOptions {
+            length: 20,
+            symbol_set: "#@".to_string(),
+            exclude_ambiguous: true,
+            min_per_class: 3,
+            ..Default::default()
+        };
+
+        for _ in 0..200 {
+            let password = generate_password(&options).unwrap();
+            assert_eq!(password.chars().count(), 20);
+            let count = |class: fn(&char) -> bool| password.chars().filter(class).count();
+            assert!(count(char::is_ascii_uppercase) >= 3, "{}", password.as_str());
+            assert!(count(char::is_ascii_lowercase) >= 3, "{}", password.as_str());
+            assert!(count(char::is_ascii_digit) >= 3, "{}", password.as_str());
+            assert!(password.chars().filter(|c| "#@".contains(*c)).count() >= 3, "{}", password.as_str());
+            assert!(password.chars().all(|c| c.is_ascii_alphanumeric() || "#@".contains(c)));
+            assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)), "{}", password.as_str());
+        }
+
+        // Solo le classi attive
+        let digits_only = GeneratorOptions {
+            uppercase: false,
+            lowercase: false,
+            symbols: false,
+            ..options
+        };
+        let password = generate_password(&digits_only).unwrap();
+        assert!(password.chars().all(|c| c.is_ascii_digit() && c != '0' && c != '1'));
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        let valid = GeneratorOptions::default();
+        for length in [MIN_LENGTH, MAX_LENGTH] {
+            let options = GeneratorOptions { length, ..valid.clone() };
+            assert_eq!(generate_password(&options).unwrap().chars().count(), length);
+        }
+
+        let invalid = [
+            GeneratorOptions { length: MIN_LENGTH - 1, ..valid.clone() },
+            GeneratorOptions { length: MAX_LENGTH + 1, ..valid.clone() },
+            GeneratorOptions {
+                uppercase: false,
+                lowercase: false,
+                digits: false,
+                symbols: false,
+                ..valid.clone()
+            },
+            GeneratorOptions { symbol_set: " ".to_string(), ..valid.clone() },
+            GeneratorOptions { length: 8, min_per_class: 3, ..valid.clone() },
+        ];
+        for options in invalid {
+            assert!(options.validate().is_err(), "{:?}", options);
+            assert!(generate_password(&options).is_err(), "{:?}", options);
+        }
+    }
+
+    #[test]
+    fn eff_wordlist_is_complete() {
+        let words = eff_words().unwrap();
+        assert_eq!(words.len(), EFF_WORD_COUNT);
+        let mut unique = words.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), EFF_WORD_COUNT);
+        assert_eq!((words[0], words[EFF_WORD_COUNT - 1]), ("abacus", "zoom"));
+    }
+
+    #[test]
+    fn passphrase_follows_options() {
+        let mut options = GeneratorOptions {
+            mode: GeneratorMode::Passphrase,
+            symbol_set: "#".to_string(),
+            ..Default::default()
+        };
+        options.passphrase = PassphraseOptions {
+            words: 7,
+            separator: "_".to_string(),
+            capitalize: true,
+            add_digit: true,
+            add_symbol: true,
+        };
+
+        let password = generate_password(&options).unwrap();
+        let words: Vec<&str> = password.split('_').collect();
+        assert_eq!(words.len(), 7);
+        assert!(words.iter().all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+        assert_eq!(password.chars().filter(char::is_ascii_digit).count(), 1);
+        assert_eq!(password.matches('#').count(), 1);
+
+        // Alcune parole EFF contengono un trattino: qui si separa con lo spazio
+        options.passphrase = PassphraseOptions {
+            words: MIN_WORDS,
+            separator: " ".to_string(),
+            ..Default::default()
+        };
+        let password = generate_password(&options).unwrap();
+        assert_eq!(password.split(' ').count(), MIN_WORDS);
+        assert!(!password.chars().any(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '#'));
+    }
 }
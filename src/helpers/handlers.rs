@@ -1,14 +1,82 @@
 use crate::helpers::utils::{
-    PasswordEntry, UserData, derive_key, encrypt_password, generate_salt, hash_password, save_data,
+    AppData, EntryExtras, Folder, GeneratorProfile, KdfParams, PasswordEntry, UNLOCK_PASSWORD, UserData, bind_legacy_entries,
+    decrypt_password, derive_key, encrypt_password, generate_id, generate_salt, generate_vault_key,
+    hash_password, now_timestamp, reencrypt_entries, verify_password, wrap_key,
 };
+use crate::helpers::vault::{
+    VaultError, load_vault, open_vault_key, rewrap_backups, save_data, save_data_without_backup, unlock_vault,
+};
+use crate::helpers::attachments::{
+    delete_attachment_file, load_attachment, remove_orphan_attachments, store_attachment,
+};
+use crate::helpers::generate_password::GeneratorOptions;
+use crate::helpers::otp::counter_code;
 use crate::{AppState, PasswordManagerApp};
 use eframe::egui;
+use std::fs;
+use zeroize::Zeroize;
 
 impl PasswordManagerApp {
     pub fn toggle_theme(&mut self) {
         self.dark_mode = !self.dark_mode;
+
+        // Prima del login il vault non può essere cifrato: il tema vale solo per la sessione
+        if self.encryption_key.is_none() {
+            return;
+        }
+
+        let previous = self.app_data.clone();
         self.app_data.dark_mode = Some(self.dark_mode);
-        save_data(&self.app_data);
+        if !self.commit(previous) {
+            self.dark_mode = !self.dark_mode;
+        }
+    }
+
+    pub fn persist(&self) -> Result<(), VaultError> {
+        let encryption_key = self.encryption_key.as_ref().ok_or_else(|| {
+            VaultError::Crypto("chiave di crittografia non disponibile".to_string())
+        })?;
+        save_data(&self.app_data, encryption_key)
+    }
+
+    // Salva il vault; se la scrittura fallisce ripristina i dati precedenti e mostra l'errore
+    pub fn commit(&mut self, previous: AppData) -> bool {
+        match self.persist() {
+            Ok(()) => true,
+            Err(e) => {
+                self.app_data = previous;
+                self.show_vault_error(&e);
+                false
+            }
+        }
+    }
+
+    // Registra l'ultimo utilizzo di un'entry. Non è un dato critico: niente rollback né backup
+    pub fn mark_used(&mut self, entry_id: &str) {
+        let Some(entry) = self.app_data.entry_mut(entry_id) else {
+            return;
+        };
+        entry.last_used_at = Some(now_timestamp());
+
+        let Some(encryption_key) = &self.encryption_key else {
+            return;
+        };
+        if let Err(e) = save_data_without_backup(&self.app_data, encryption_key) {
+            self.message = format!("Impossibile registrare l'ultimo utilizzo: {}", e);
+            self.message_color = egui::Color32::YELLOW;
+        }
+    }
+
+    // Da chiamare dopo ogni salvataggio che può lasciare allegati senza entry
+    pub fn remove_unused_attachments(&self) {
+        if let Some(encryption_key) = &self.encryption_key {
+            remove_orphan_attachments(&self.app_data, encryption_key);
+        }
+    }
+
+    pub fn show_vault_error(&mut self, e: &VaultError) {
+        self.message = format!("Salvataggio non riuscito! {}", e);
+        self.message_color = egui::Color32::RED;
     }
 
     pub fn handle_registration(&mut self) {
@@ -30,25 +98,57 @@ impl PasswordManagerApp {
             return;
         }
 
-        // Genera salt per l'hash della password e per la derivazione della chiave
-        let salt = generate_salt();
+        // Hash Argon2id della password (il salt è incluso nella stringa PHC)
+        let kdf = KdfParams::default();
+        let p_h = match hash_password(&self.reg_password, &kdf) {
+            Ok(p_h) => p_h,
+            Err(_) => {
+                self.message = "Errore nella creazione dell'hash della password!".to_string();
+                self.message_color = egui::Color32::RED;
+                return;
+            }
+        };
+
+        // Genera salt per la derivazione della chiave
         let key_salt = generate_salt();
-        let p_h = hash_password(&self.reg_password, &salt);
 
-        // Deriva la chiave di crittografia dalla password
-        self.encryption_key = Some(derive_key(&self.reg_password, &key_salt));
+        // La chiave casuale cifra i dati, quella derivata dalla password cifra solo lei
+        let vault_key = generate_vault_key();
+        let wrapped_key = match derive_key(&self.reg_password, &key_salt, &kdf)
+            .and_then(|derived_key| wrap_key(&vault_key, UNLOCK_PASSWORD, &derived_key))
+        {
+            Ok(wrapped_key) => wrapped_key,
+            Err(_) => {
+                self.message = "Errore nella crittografia della chiave!".to_string();
+                self.message_color = egui::Color32::RED;
+                return;
+            }
+        };
+
+        self.encryption_key = Some(vault_key);
+        let previous = self.app_data.clone();
 
         let user_data = UserData {
             u: self.reg_username.clone(),
+            kdf,
             p_h,
-            salt,
+            salt: String::new(),
             key_salt,
+            wrapped_keys: vec![wrapped_key],
+            vault_id: generate_id(),
         };
 
         self.app_data.user = Some(user_data.clone());
-        self.current_user = Some(user_data);
+        self.app_data.dark_mode = Some(self.dark_mode);
 
-        save_data(&self.app_data);
+        if !self.commit(previous) {
+            self.encryption_key = None;
+            return;
+        }
+
+        self.current_user = Some(user_data);
+        self.settings_draft = self.app_data.settings.clone();
+        self.kdf_draft = self.app_data.user.as_ref().map(|user| user.kdf.clone()).unwrap_or_default();
 
         self.message = "Registrazione completata con successo!".to_string();
         self.message_color = egui::Color32::GREEN;
@@ -56,27 +156,154 @@ impl PasswordManagerApp {
 
         // Pulisci i campi
         self.reg_username.clear();
-        self.reg_password.clear();
-        self.reg_confirm_password.clear();
+        self.reg_password.zeroize();
+        self.reg_confirm_password.zeroize();
     }
 
     pub fn handle_login(&mut self) {
-        if let Some(user) = &self.app_data.user {
-            let p_h = hash_password(&self.login_password, &user.salt);
+        let Some(locked) = &self.locked_vault else {
+            return;
+        };
+
+        if !verify_password(&self.login_password, &locked.header.p_h, &locked.header.salt) {
+            self.message = "Username o password non corretti!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        // Deriva la chiave dalla password, recupera quella del vault e aprilo
+        let derived_key =
+            match derive_key(&self.login_password, &locked.header.key_salt, &locked.header.kdf) {
+                Ok(derived_key) => derived_key,
+                Err(e) => {
+                    self.message = format!("Impossibile derivare la chiave! {}", e);
+                    self.message_color = egui::Color32::RED;
+                    return;
+                }
+            };
+        let (mut encryption_key, mut app_data) = match open_vault_key(&locked.header, &derived_key)
+            .and_then(|vault_key| {
+                let app_data = unlock_vault(locked, &vault_key)?;
+                Ok((vault_key, app_data))
+            })
+        {
+            Ok(opened) => opened,
+            // La password è giusta: se il contenuto non si decifra o non si legge il file è danneggiato,
+            // si passa alla schermata che permette di ripristinare un backup
+            Err(e @ (VaultError::Crypto(_) | VaultError::Parse(_))) => {
+                self.message = format!("Impossibile aprire il vault! {}", e);
+                self.message_color = egui::Color32::RED;
+                self.load_error = Some(e);
+                return;
+            }
+            Err(e) => {
+                self.message = format!("Impossibile aprire il vault! {}", e);
+                self.message_color = egui::Color32::RED;
+                return;
+            }
+        };
 
-            if self.login_username == user.u && p_h == user.p_h {
-                // Deriva la chiave di crittografia dalla password
-                self.encryption_key = Some(derive_key(&self.login_password, &user.key_salt));
+        match app_data.user.clone() {
+            Some(mut user) if user.u == self.login_username => {
+                let mut needs_save = !locked.is_sealed();
+
+                // Vault o entry senza ID: assegnali e lega ogni password alla sua entry
+                if user.vault_id.is_empty() {
+                    user.vault_id = generate_id();
+                    needs_save = true;
+                }
+                match bind_legacy_entries(&mut app_data.ps, &encryption_key, &user.vault_id) {
+                    Ok(changed) => needs_save |= changed,
+                    Err(e) => {
+                        self.message = format!("Impossibile aggiornare il vault! {}", e);
+                        self.message_color = egui::Color32::RED;
+                        return;
+                    }
+                }
+
+                // Vault senza chiave casuale: generala e ricifra le password con lei
+                if user.wrapped_keys.is_empty() {
+                    let vault_key = generate_vault_key();
+                    let migrated = reencrypt_entries(
+                        &mut app_data.ps,
+                        &encryption_key,
+                        &vault_key,
+                        &user.vault_id,
+                    )
+                        .and_then(|_| wrap_key(&vault_key, UNLOCK_PASSWORD, &derived_key));
+
+                    match migrated {
+                        Ok(wrapped_key) => {
+                            user.wrapped_keys.push(wrapped_key);
+                            encryption_key = vault_key;
+                            needs_save = true;
+                        }
+                        Err(e) => {
+                            self.message = format!("Impossibile aggiornare il vault! {}", e);
+                            self.message_color = egui::Color32::RED;
+                            return;
+                        }
+                    }
+                }
+
+                // Campi di tipo salvati in chiaro prima che diventassero segreti
+                match app_data.seal_typed_fields(&encryption_key, &user.vault_id) {
+                    Ok(changed) => needs_save |= changed,
+                    Err(e) => {
+                        self.message = format!("Impossibile aggiornare il vault! {}", e);
+                        self.message_color = egui::Color32::RED;
+                        return;
+                    }
+                }
+
+                // Aggiorna ad Argon2id il vecchio hash SHA-256 e quelli creati con un'altra variante Argon2
+                if !user.p_h.starts_with("$argon2id$")
+                    && let Ok(p_h) = hash_password(&self.login_password, &user.kdf)
+                {
+                    user.p_h = p_h;
+                    user.salt.clear();
+                    needs_save = true;
+                }
+
+                // Svuota il cestino dalle entry oltre il periodo di conservazione
+                if app_data.purge_expired_trash() > 0 {
+                    needs_save = true;
+                }
 
                 self.current_user = Some(user.clone());
+                self.dark_mode = app_data.dark_mode.unwrap_or(self.dark_mode);
+                self.app_data = app_data;
+                self.app_data.user = Some(user);
+                self.encryption_key = Some(encryption_key);
+                self.settings_draft = self.app_data.settings.clone();
+                self.kdf_draft = self.app_data.user.as_ref().map(|user| user.kdf.clone()).unwrap_or_default();
+
                 self.state = AppState::Main;
                 self.message = "Accesso effettuato con successo!".to_string();
                 self.message_color = egui::Color32::GREEN;
 
+                // Vault migrato o hash aggiornato: riscrivi subito il file.
+                // Se non riesce i dati in memoria restano validi e verranno salvati al prossimo giro
+                let saved = match needs_save.then(|| self.persist()) {
+                    Some(Err(e)) => {
+                        self.message = format!("Accesso effettuato, ma il vault non è stato aggiornato: {}", e);
+                        self.message_color = egui::Color32::YELLOW;
+                        false
+                    }
+                    _ => true,
+                };
+
+                // File di allegati rimasti da entry eliminate dal cestino, solo se il file
+                // su disco non li usa più
+                if saved {
+                    self.remove_unused_attachments();
+                }
+
                 // Pulisci i campi
                 self.login_username.clear();
-                self.login_password.clear();
-            } else {
+                self.login_password.zeroize();
+            }
+            _ => {
                 self.message = "Username o password non corretti!".to_string();
                 self.message_color = egui::Color32::RED;
             }
@@ -84,33 +311,76 @@ impl PasswordManagerApp {
     }
 
     pub fn add_password(&mut self) {
-        if self.new_entry_name.is_empty() || self.new_entry_password.is_empty() {
+        if self.new_entry_name.is_empty() {
+            self.message = "Il nome è obbligatorio!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        // Solo i login hanno una password; gli altri tipi cifrano i propri campi
+        let has_password = self.new_entry_extras.kind.has_password();
+        if has_password && self.new_entry_password.is_empty() {
             self.message = "Nome servizio e password sono obbligatori!".to_string();
             self.message_color = egui::Color32::RED;
             return;
         }
+        if !has_password {
+            self.new_entry_password.zeroize();
+            self.new_entry_username.clear();
+        }
 
         // Cripta la password
         if let Some(encryption_key) = &self.encryption_key {
-            match encrypt_password(&self.new_entry_password, encryption_key) {
-                Ok((e_c, nonce)) => {
-                    let entry = PasswordEntry {
+            let id = generate_id();
+            let vault_id = self.app_data.vault_id();
+            let now = now_timestamp();
+            let result = encrypt_password(&self.new_entry_password, encryption_key, vault_id, &id)
+                .and_then(|(e_c, nonce)| {
+                    let mut entry = PasswordEntry {
+                        id,
                         name: self.new_entry_name.clone(),
                         u: self.new_entry_username.clone(),
                         e_c,
                         nonce,
+                        created_at: Some(now),
+                        modified_at: Some(now),
+                        ..Default::default()
                     };
+                    self.new_entry_extras.apply_to(&mut entry, encryption_key, vault_id)?;
+                    Ok(entry)
+                });
+
+            match result {
+                Ok(entry) => {
 
+                    // I duplicati sono permessi (es. account personale e di lavoro), ma li segnaliamo
+                    let duplicates = self.app_data.entries_named(&self.new_entry_name).count();
+
+                    let previous = self.app_data.clone();
                     self.app_data.ps.push(entry);
-                    save_data(&self.app_data);
+                    if !self.commit(previous) {
+                        return;
+                    }
 
-                    self.message = "Password aggiunta con successo!".to_string();
-                    self.message_color = egui::Color32::GREEN;
+                    if duplicates > 0 {
+                        self.message = format!(
+                            "Voce aggiunta, ma esistono già {} voci chiamate '{}'",
+                            duplicates, self.new_entry_name
+                        );
+                        self.message_color = egui::Color32::YELLOW;
+                    } else {
+                        self.message = "Voce aggiunta con successo!".to_string();
+                        self.message_color = egui::Color32::GREEN;
+                    }
 
                     // Pulisci i campi
                     self.new_entry_name.clear();
                     self.new_entry_username.clear();
-                    self.new_entry_password.clear();
+                    self.new_entry_password.zeroize();
+                    self.new_entry_extras = EntryExtras {
+                        folder: self.selected_folder.clone(),
+                        ..Default::default()
+                    };
                 }
                 Err(_) => {
                     self.message = "Errore nella crittografia della password!".to_string();
@@ -123,80 +393,657 @@ impl PasswordManagerApp {
         }
     }
 
+    // Carica l'entry nell'editor con i valori attuali, password compresa
+    pub fn start_editing(&mut self, entry_id: &str) {
+        let Some(entry) = self.app_data.entry(entry_id) else {
+            return;
+        };
+
+        let Some(encryption_key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
+
+        let vault_id = self.app_data.vault_id();
+        match decrypt_password(entry, encryption_key, vault_id).and_then(|password| {
+            let extras = EntryExtras::from_entry(entry, encryption_key, vault_id)?;
+            Ok((password, extras))
+        }) {
+            Ok((password, extras)) => {
+                self.edit_entry_extras = extras;
+                self.edit_entry_name = entry.name.clone();
+                self.edit_entry_username = entry.u.clone();
+                // Copia nei buffer esistenti: hanno già la capacità per non riallocare durante la modifica
+                self.edit_new_password.clear();
+                self.edit_new_password.push_str(&password);
+                self.edit_confirm_password.clear();
+                self.edit_confirm_password.push_str(&password);
+                self.edit_entry_id = Some(entry.id.clone());
+                self.show_popup_edit = false;
+            }
+            Err(_) => {
+                self.message = "Errore nella decrittografia!".to_string();
+                self.message_color = egui::Color32::RED;
+            }
+        }
+    }
+
+    pub fn cancel_editing(&mut self) {
+        self.edit_entry_id = None;
+        self.edit_entry_name.clear();
+        self.edit_entry_username.clear();
+        self.edit_new_password.zeroize();
+        self.edit_confirm_password.zeroize();
+        self.edit_entry_extras = EntryExtras::default();
+        self.show_popup_edit = false;
+    }
+
     pub fn edit_password(&mut self) {
-        if self.edit_service_name.is_empty() || self.edit_new_password.is_empty() {
-            self.message = "Nome servizio e nuova password sono obbligatori!".to_string();
+        let Some(entry_id) = self.edit_entry_id.clone() else {
+            self.message = "Seleziona la password da modificare!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
+
+        let service_name = self.edit_entry_name.trim().to_string();
+        if service_name.is_empty() {
+            self.message = "Il nome è obbligatorio!".to_string();
             self.message_color = egui::Color32::RED;
             return;
         }
 
+        let has_password = self.edit_entry_extras.kind.has_password();
+        if has_password && self.edit_new_password.is_empty() {
+            self.message = "Nome servizio e password sono obbligatori!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+        if !has_password {
+            // Un login convertito in un altro tipo perde username e password
+            self.edit_new_password.zeroize();
+            self.edit_confirm_password.zeroize();
+            self.edit_entry_username.clear();
+        }
+
         if self.edit_new_password != self.edit_confirm_password {
             self.message = "Le password non coincidono!".to_string();
             self.message_color = egui::Color32::RED;
             return;
         }
 
-        // Trova l'entry da modificare
-        let entry_index =
-            self.app_data.ps.iter().position(|entry| {
-                entry.name.to_lowercase() == self.edit_service_name.to_lowercase()
-            });
+        // Controlla che l'entry esista ancora
+        let Some(entry) = self.app_data.entry(&entry_id) else {
+            self.message = "La password selezionata non esiste più!".to_string();
+            self.message_color = egui::Color32::RED;
+            self.cancel_editing();
+            return;
+        };
 
-        match entry_index {
-            Some(index) => {
-                if let Some(encryption_key) = &self.encryption_key {
-                    match encrypt_password(&self.edit_new_password, encryption_key) {
-                        Ok((e_c, nonce)) => {
-                            // Modifica l'entry esistente
-                            let entry = &mut self.app_data.ps[index];
-                            entry.e_c = e_c;
-                            entry.nonce = nonce;
+        let Some(encryption_key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
 
-                            // Modifica l'username solo se è stato specificato
-                            if !self.edit_new_username.is_empty() {
-                                entry.u = self.edit_new_username.clone();
-                            }
+        // La cronologia registra solo i cambi di password, non quelli di nome o username
+        let password_changed = !decrypt_password(entry, encryption_key, self.app_data.vault_id())
+            .is_ok_and(|current| current == self.edit_new_password);
 
-                            // Rimuovi dalla lista delle password mostrate se presente
-                            self.shown_passwords.remove(&index);
+        let depth = self.app_data.settings.history_depth;
+        let mut updated = entry.clone();
+        updated.name = service_name.clone();
+        updated.u = self.edit_entry_username.clone();
+        updated.modified_at = Some(now_timestamp());
 
-                            save_data(&self.app_data);
+        let result = encrypt_password(
+            &self.edit_new_password,
+            encryption_key,
+            self.app_data.vault_id(),
+            &entry_id,
+        )
+        .and_then(|(e_c, nonce)| {
+            if password_changed {
+                updated.replace_password(e_c, nonce, depth);
+            }
+            self.edit_entry_extras.apply_to(&mut updated, encryption_key, self.app_data.vault_id())
+        });
 
-                            self.message = format!(
-                                "Password di '{}' modificata con successo!",
-                                self.edit_service_name
-                            );
-                            self.message_color = egui::Color32::GREEN;
+        match result {
+            Ok(()) => {
+                // Modifica l'entry esistente
+                let previous = self.app_data.clone();
+                if let Some(entry) = self.app_data.entry_mut(&entry_id) {
+                    *entry = updated;
+                }
 
-                            // Pulisci i campi
-                            self.edit_service_name.clear();
-                            self.edit_new_username.clear();
-                            self.edit_new_password.clear();
-                            self.edit_confirm_password.clear();
-                        }
-                        Err(_) => {
-                            self.message = "Errore nella crittografia della password!".to_string();
-                            self.message_color = egui::Color32::RED;
-                        }
-                    }
-                } else {
-                    self.message = "Chiave di crittografia non disponibile!".to_string();
-                    self.message_color = egui::Color32::RED;
+                if !self.commit(previous) {
+                    return;
                 }
+
+                // Rimuovi dalla lista delle password mostrate se presente
+                self.shown_passwords.remove(&entry_id);
+
+                self.message = format!("'{}' modificata con successo!", service_name);
+                self.message_color = egui::Color32::GREEN;
+
+                // Pulisci i campi
+                self.cancel_editing();
             }
-            None => {
-                self.message = format!("Servizio '{}' non trovato!", self.edit_service_name);
+            Err(_) => {
+                self.message = "Errore nella crittografia della password!".to_string();
                 self.message_color = egui::Color32::RED;
             }
         }
     }
 
+    pub fn restore_previous_password(&mut self, entry_id: &str, index: usize) {
+        let depth = self.app_data.settings.history_depth;
+        let previous = self.app_data.clone();
+        let Some(entry) = self.app_data.entry_mut(entry_id) else {
+            return;
+        };
+        if !entry.restore_previous(index, depth) {
+            return;
+        }
+        entry.modified_at = Some(now_timestamp());
+        let name = entry.name.clone();
+
+        if !self.commit(previous) {
+            return;
+        }
+
+        self.shown_passwords.remove(entry_id);
+        // L'editor aperto su questa entry mostrerebbe la password sostituita
+        if self.edit_entry_id.as_deref() == Some(entry_id) {
+            self.start_editing(entry_id);
+        }
+
+        self.message = format!("Password precedente di '{}' ripristinata!", name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    // Il contatore avanza e viene salvato prima di mostrare il codice:
+    // se il salvataggio fallisce il codice non viene usato e il contatore resta com'era
+    pub fn next_hotp_code(&mut self, entry_id: &str) -> Option<String> {
+        let Some(key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return None;
+        };
+        let vault_id = self.app_data.vault_id().to_string();
+        let previous = self.app_data.clone();
+        let entry = self.app_data.entry_mut(entry_id)?;
+        let otp = entry.otp.as_ref()?;
+
+        let code = match counter_code(entry, otp, key, &vault_id) {
+            Ok(code) => code,
+            Err(_) => {
+                self.message = "Errore nella decrittografia!".to_string();
+                self.message_color = egui::Color32::RED;
+                return None;
+            }
+        };
+        if let Some(otp) = entry.otp.as_mut() {
+            otp.counter += 1;
+        }
+        entry.last_used_at = Some(now_timestamp());
+
+        // Il contatore cambia a ogni codice: niente rotazione, altrimenti i backup veri sparirebbero
+        let Some(encryption_key) = &self.encryption_key else {
+            return None;
+        };
+        match save_data_without_backup(&self.app_data, encryption_key) {
+            Ok(()) => Some(code),
+            Err(e) => {
+                self.app_data = previous;
+                self.show_vault_error(&e);
+                None
+            }
+        }
+    }
+
+    pub fn create_folder(&mut self) {
+        let name = self.folder_name_input.trim().to_string();
+        if name.is_empty() {
+            self.message = "Inserisci il nome della cartella!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        // La nuova cartella va dentro quella selezionata
+        let folder = Folder {
+            id: generate_id(),
+            name: name.clone(),
+            parent: self.selected_folder.clone(),
+        };
+        let id = folder.id.clone();
+
+        let previous = self.app_data.clone();
+        self.app_data.folders.push(folder);
+        if !self.commit(previous) {
+            return;
+        }
+
+        self.selected_folder = Some(id);
+        self.new_entry_extras.folder = self.selected_folder.clone();
+        self.folder_name_input.clear();
+        self.message = format!("Cartella '{}' creata!", name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    pub fn rename_folder(&mut self, folder_id: &str) {
+        let name = self.folder_name_input.trim().to_string();
+        if name.is_empty() {
+            self.message = "Inserisci il nuovo nome della cartella!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        let previous = self.app_data.clone();
+        let Some(folder) = self.app_data.folders.iter_mut().find(|folder| folder.id == folder_id) else {
+            return;
+        };
+        folder.name = name.clone();
+        if !self.commit(previous) {
+            return;
+        }
+
+        self.folder_name_input.clear();
+        self.message = format!("Cartella rinominata in '{}'!", name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    pub fn delete_folder(&mut self, folder_id: &str) {
+        let previous = self.app_data.clone();
+        let parent = self.app_data.folder(folder_id).and_then(|folder| folder.parent.clone());
+        let Some(name) = self.app_data.delete_folder(folder_id) else {
+            return;
+        };
+        if !self.commit(previous) {
+            return;
+        }
+
+        self.selected_folder = parent;
+        self.new_entry_extras.folder = self.selected_folder.clone();
+        if self.edit_entry_extras.folder.as_deref() == Some(folder_id) {
+            self.edit_entry_extras.folder = self.selected_folder.clone();
+        }
+        self.message = format!("Cartella '{}' eliminata!", name);
+        self.message_color = egui::Color32::YELLOW;
+    }
+
+    pub fn save_generator_profile(&mut self) {
+        let name = self.profile_name_input.trim().to_string();
+        if name.is_empty() {
+            self.message = "Inserisci il nome del profilo!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+        if let Err(e) = self.profile_draft.validate() {
+            self.message = e;
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        let previous = self.app_data.clone();
+        let existing = self
+            .editing_profile_id
+            .as_deref()
+            .and_then(|id| self.app_data.profiles.iter_mut().find(|profile| profile.id == id));
+        match existing {
+            Some(profile) => {
+                profile.name = name.clone();
+                profile.options = self.profile_draft.clone();
+            }
+            None => self.app_data.profiles.push(GeneratorProfile {
+                id: generate_id(),
+                name: name.clone(),
+                options: self.profile_draft.clone(),
+            }),
+        }
+        if !self.commit(previous) {
+            return;
+        }
+
+        self.cancel_profile_editing();
+        self.message = format!("Profilo '{}' salvato!", name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    pub fn start_profile_editing(&mut self, profile_id: &str) {
+        let Some(profile) = self.app_data.profile(profile_id) else {
+            return;
+        };
+        self.profile_name_input = profile.name.clone();
+        self.profile_draft = profile.options.clone();
+        self.editing_profile_id = Some(profile.id.clone());
+    }
+
+    pub fn cancel_profile_editing(&mut self) {
+        self.editing_profile_id = None;
+        self.profile_name_input.clear();
+        self.profile_draft = GeneratorOptions::default();
+    }
+
+    pub fn delete_generator_profile(&mut self, profile_id: &str) {
+        let previous = self.app_data.clone();
+        let Some(name) = self.app_data.delete_profile(profile_id) else {
+            return;
+        };
+        if !self.commit(previous) {
+            return;
+        }
+
+        // I form aperti non devono puntare a un profilo che non esiste più
+        for extras in [&mut self.new_entry_extras, &mut self.edit_entry_extras] {
+            if extras.generator_profile.as_deref() == Some(profile_id) {
+                extras.generator_profile = None;
+            }
+        }
+        if self.editing_profile_id.as_deref() == Some(profile_id) {
+            self.cancel_profile_editing();
+        }
+        self.message = format!("Profilo '{}' eliminato!", name);
+        self.message_color = egui::Color32::YELLOW;
+    }
+
+    pub fn move_entry_to_folder(&mut self, entry_id: &str, folder: Option<String>) {
+        let previous = self.app_data.clone();
+        let folder_name = folder
+            .as_deref()
+            .map_or("Nessuna cartella".to_string(), |folder| self.app_data.folder_path(folder));
+        let Some(entry) = self.app_data.entry_mut(entry_id) else {
+            return;
+        };
+        if entry.folder == folder {
+            return;
+        }
+        entry.folder = folder.clone();
+        entry.modified_at = Some(now_timestamp());
+        let name = entry.name.clone();
+
+        if !self.commit(previous) {
+            return;
+        }
+
+        // L'editor aperto sulla stessa entry non deve riportarla nella cartella precedente
+        if self.edit_entry_id.as_deref() == Some(entry_id) {
+            self.edit_entry_extras.folder = folder;
+        }
+        self.message = format!("'{}' spostata in '{}'", name, folder_name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    pub fn attach_file(&mut self, entry_id: &str) {
+        let Some(source) = rfd::FileDialog::new()
+            .set_title("Scegli il file da allegare")
+            .pick_file()
+        else {
+            return;
+        };
+
+        let Some(encryption_key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
+
+        let limit = self.app_data.settings.attachment_limit_mb * 1024 * 1024;
+        let attachment = match store_attachment(&source, limit, encryption_key, self.app_data.vault_id(), entry_id) {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                self.message = format!("Impossibile allegare il file! {}", e);
+                self.message_color = egui::Color32::RED;
+                return;
+            }
+        };
+
+        let previous = self.app_data.clone();
+        let Some(entry) = self.app_data.entry_mut(entry_id) else {
+            let _ = delete_attachment_file(&attachment.id);
+            return;
+        };
+        let name = attachment.name.clone();
+        let attachment_id = attachment.id.clone();
+        entry.attachments.push(attachment);
+        entry.modified_at = Some(now_timestamp());
+
+        // Il file cifrato è già scritto: se il vault non si salva non serve più
+        if !self.commit(previous) {
+            let _ = delete_attachment_file(&attachment_id);
+            return;
+        }
+
+        self.message = format!("'{}' allegato!", name);
+        self.message_color = egui::Color32::GREEN;
+    }
+
+    pub fn save_attachment_as(&mut self, entry_id: &str, attachment_id: &str) {
+        let Some(entry) = self.app_data.entry(entry_id) else {
+            return;
+        };
+        let Some(attachment) = entry.attachments.iter().find(|attachment| attachment.id == attachment_id) else {
+            return;
+        };
+        let Some(encryption_key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
+
+        let Some(destination) = rfd::FileDialog::new()
+            .set_title("Salva allegato")
+            .set_file_name(&attachment.name)
+            .save_file()
+        else {
+            return;
+        };
+
+        let name = attachment.name.clone();
+        match load_attachment(attachment, encryption_key, self.app_data.vault_id(), entry_id)
+            .and_then(|contents| fs::write(&destination, &*contents).map_err(|e| VaultError::io(&destination, e)))
+        {
+            Ok(()) => {
+                self.message = format!("'{}' salvato in {}", name, destination.display());
+                self.message_color = egui::Color32::GREEN;
+                self.mark_used(entry_id);
+            }
+            Err(e) => {
+                self.message = format!("Impossibile salvare l'allegato! {}", e);
+                self.message_color = egui::Color32::RED;
+            }
+        }
+    }
+
+    pub fn remove_attachment(&mut self, entry_id: &str, attachment_id: &str) {
+        let previous = self.app_data.clone();
+        let Some(entry) = self.app_data.entry_mut(entry_id) else {
+            return;
+        };
+        let Some(position) = entry.attachments.iter().position(|attachment| attachment.id == attachment_id) else {
+            return;
+        };
+        let removed = entry.attachments.remove(position);
+        entry.modified_at = Some(now_timestamp());
+
+        if !self.commit(previous) {
+            return;
+        }
+
+        // Il file si elimina solo quando né il vault né i backup lo usano più
+        self.remove_unused_attachments();
+
+        self.message = format!("Allegato '{}' eliminato!", removed.name);
+        self.message_color = egui::Color32::RED;
+    }
+
+    pub fn change_master_password(&mut self) {
+        let Some(user) = &self.app_data.user else {
+            return;
+        };
+
+        if !verify_password(&self.change_old_password, &user.p_h, &user.salt) {
+            self.message = "La password attuale non è corretta!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        if self.change_new_password != self.change_confirm_password {
+            self.message = "Le password non coincidono!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        if self.change_new_password.len() < 6 {
+            self.message = "La password deve essere di almeno 6 caratteri!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        let new_password = self.change_new_password.clone();
+        let kdf = user.kdf.clone();
+        if !self.rewrap_vault_key(&new_password, kdf) {
+            return;
+        }
+
+        self.message = "Master password cambiata con successo!".to_string();
+        self.message_color = egui::Color32::GREEN;
+
+        // Pulisci i campi
+        self.change_old_password.zeroize();
+        self.change_new_password.zeroize();
+        self.change_confirm_password.zeroize();
+    }
+
+    pub fn apply_kdf_params(&mut self) {
+        let Some(user) = &self.app_data.user else {
+            return;
+        };
+
+        if !verify_password(&self.kdf_password, &user.p_h, &user.salt) {
+            self.message = "La password attuale non è corretta!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        if let Err(e) = self.kdf_draft.validate() {
+            self.message = e;
+            self.message_color = egui::Color32::RED;
+            return;
+        }
+
+        let password = self.kdf_password.clone();
+        if !self.rewrap_vault_key(&password, self.kdf_draft.clone()) {
+            return;
+        }
+
+        self.message = "Parametri di derivazione aggiornati!".to_string();
+        self.message_color = egui::Color32::GREEN;
+        self.kdf_password.zeroize();
+    }
+
+    // Ricalcola hash e chiave derivata (con un salt nuovo) e ricifra con lei la chiave del vault.
+    // Le password non vanno ricifrate: cambia solo l'intestazione
+    fn rewrap_vault_key(&mut self, password: &str, kdf: KdfParams) -> bool {
+        let (Some(user), Some(vault_key)) = (&self.app_data.user, &self.encryption_key) else {
+            return false;
+        };
+
+        let p_h = match hash_password(password, &kdf) {
+            Ok(p_h) => p_h,
+            Err(_) => {
+                self.message = "Errore nella creazione dell'hash della password!".to_string();
+                self.message_color = egui::Color32::RED;
+                return false;
+            }
+        };
+
+        let key_salt = generate_salt();
+        let wrapped_key = match derive_key(password, &key_salt, &kdf)
+            .and_then(|derived_key| wrap_key(vault_key, UNLOCK_PASSWORD, &derived_key))
+        {
+            Ok(wrapped_key) => wrapped_key,
+            Err(_) => {
+                self.message = "Errore nella crittografia della chiave!".to_string();
+                self.message_color = egui::Color32::RED;
+                return false;
+            }
+        };
+
+        let mut wrapped_keys: Vec<_> = user
+            .wrapped_keys
+            .iter()
+            .filter(|wrapped| wrapped.method != UNLOCK_PASSWORD)
+            .cloned()
+            .collect();
+        wrapped_keys.push(wrapped_key);
+
+        let previous = self.app_data.clone();
+        self.app_data.user = Some(UserData {
+            u: user.u.clone(),
+            kdf,
+            p_h,
+            salt: String::new(),
+            key_salt,
+            wrapped_keys,
+            vault_id: user.vault_id.clone(),
+        });
+
+        if !self.commit(previous) {
+            return false;
+        }
+        self.current_user = self.app_data.user.clone();
+
+        // I backup contengono ancora la chiave cifrata con la vecchia password
+        if let (Some(user), Some(vault_key)) = (&self.app_data.user, &self.encryption_key)
+            && let Err(e) = rewrap_backups(user, vault_key)
+        {
+            self.message = format!("Attenzione: i backup si possono ancora aprire con la vecchia password! {}", e);
+            self.message_color = egui::Color32::YELLOW;
+            return false;
+        }
+        true
+    }
+
     pub fn logout(&mut self) {
         self.current_user = None;
         self.encryption_key = None;
+        self.app_data = AppData::default();
         self.shown_passwords.clear();
-        self.state = AppState::Login;
+        self.show_trash = false;
+        self.details_entry_id = None;
+        self.selected_folder = None;
+        self.folder_name_input.clear();
+        self.new_entry_password.zeroize();
+        self.new_entry_extras = EntryExtras::default();
+        self.cancel_editing();
+        self.cancel_profile_editing();
+        self.change_old_password.zeroize();
+        self.change_new_password.zeroize();
+        self.change_confirm_password.zeroize();
+        self.kdf_password.zeroize();
+        self.reload_vault();
         self.message = "Logout effettuato con successo.".to_string();
         self.message_color = egui::Color32::CYAN;
     }
+
+    pub fn reload_vault(&mut self) {
+        match load_vault() {
+            Ok(locked_vault) => {
+                self.locked_vault = locked_vault;
+                self.load_error = None;
+            }
+            Err(e) => {
+                self.locked_vault = None;
+                self.load_error = Some(e);
+            }
+        }
+
+        self.state = if self.locked_vault.is_some() || self.load_error.is_some() {
+            AppState::Login
+        } else {
+            AppState::Registration
+        };
+    }
 }
@@ -1,3 +1,6 @@
 pub mod utils;
 pub mod handlers;
-pub mod generate_password;
\ No newline at end of file
+pub mod generate_password;
+pub mod vault;
+pub mod attachments;
+pub mod otp;
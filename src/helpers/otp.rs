@@ -0,0 +1,400 @@
+use crate::helpers::utils::{OtpSecret, PasswordEntry, SecretString, decrypt_otp_secret};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    pub const ALL: [OtpAlgorithm; 3] = [OtpAlgorithm::Sha1, OtpAlgorithm::Sha256, OtpAlgorithm::Sha512];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.label().eq_ignore_ascii_case(label))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpKind {
+    #[default]
+    Totp, // RFC 6238, a tempo
+    Hotp, // RFC 4226, a contatore
+    Steam, // Steam Guard: TOTP SHA1 a 30 secondi con 5 caratteri alfanumerici
+}
+
+impl OtpKind {
+    pub const ALL: [OtpKind; 3] = [OtpKind::Totp, OtpKind::Hotp, OtpKind::Steam];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OtpKind::Totp => "TOTP",
+            OtpKind::Hotp => "HOTP",
+            OtpKind::Steam => "Steam Guard",
+        }
+    }
+
+    // I codici a tempo cambiano da soli, quelli a contatore solo quando vengono generati
+    pub fn is_time_based(self) -> bool {
+        self != OtpKind::Hotp
+    }
+
+    pub fn is_totp(&self) -> bool {
+        *self == OtpKind::Totp
+    }
+}
+
+// Parametri letti da un URI otpauth://totp/..., otpauth://hotp/... o steam://...
+pub struct OtpUri {
+    pub secret: SecretString, // Base32 normalizzato
+    pub kind: OtpKind,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub counter: u64,
+}
+
+pub const DEFAULT_DIGITS: u32 = 6;
+pub const DEFAULT_PERIOD: u64 = 30;
+
+const STEAM_ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+const STEAM_DIGITS: usize = 5;
+
+// Base32 RFC 4648: maiuscole/minuscole, spazi, trattini e padding vengono ignorati
+pub fn decode_base32(input: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let mut output = Zeroizing::new(Vec::with_capacity(input.len() * 5 / 8));
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for c in input.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = match c.to_ascii_uppercase() {
+            upper @ 'A'..='Z' => upper as u64 - 'A' as u64,
+            digit @ '2'..='7' => digit as u64 - '2' as u64 + 26,
+            _ => return Err(format!("carattere '{}' non valido nel segreto", c)),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if output.is_empty() {
+        return Err("segreto vuoto".to_string());
+    }
+    Ok(output)
+}
+
+// Forma con cui il segreto viene salvato: maiuscolo, senza separatori né padding
+pub fn normalize_secret(input: &str) -> Result<SecretString, String> {
+    decode_base32(input)?;
+    let mut normalized = Zeroizing::new(String::with_capacity(input.len()));
+    normalized.extend(
+        input
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '='))
+            .map(|c| c.to_ascii_uppercase()),
+    );
+    Ok(normalized)
+}
+
+pub fn parse_otpauth_uri(uri: &str) -> Result<OtpUri, String> {
+    let uri = uri.trim();
+    // Formato esportato da alcuni strumenti per Steam: steam://SEGRETO
+    if let Some(secret) = uri.strip_prefix("steam://") {
+        return Ok(OtpUri {
+            secret: normalize_secret(secret)?,
+            kind: OtpKind::Steam,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: STEAM_DIGITS as u32,
+            period: DEFAULT_PERIOD,
+            counter: 0,
+        });
+    }
+
+    let rest = uri
+        .strip_prefix("otpauth://")
+        .ok_or("l'URI deve iniziare con otpauth://")?;
+    let (kind, rest) = rest.split_once('/').ok_or("URI otpauth non valido")?;
+    let mut kind = match kind.to_ascii_lowercase().as_str() {
+        "totp" => OtpKind::Totp,
+        "hotp" => OtpKind::Hotp,
+        "steam" => OtpKind::Steam,
+        _ => return Err(format!("tipo '{}' non supportato", kind)),
+    };
+    let (label, query) = rest.split_once('?').ok_or("parametri mancanti nell'URI")?;
+
+    let mut secret = None;
+    let mut algorithm = OtpAlgorithm::default();
+    let mut digits = DEFAULT_DIGITS;
+    let mut period = DEFAULT_PERIOD;
+    let mut counter = None;
+    let mut issuer = label.split_once(':').map(|(issuer, _)| issuer.to_string());
+
+    for (name, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match name.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(normalize_secret(value)?),
+            "algorithm" => {
+                algorithm = OtpAlgorithm::from_label(value).ok_or(format!("algoritmo '{}' non supportato", value))?
+            }
+            "digits" => digits = value.parse().map_err(|_| format!("cifre '{}' non valide", value))?,
+            "period" => period = value.parse().map_err(|_| format!("periodo '{}' non valido", value))?,
+            "counter" => counter = Some(value.parse().map_err(|_| format!("contatore '{}' non valido", value))?),
+            "issuer" => issuer = Some(value.to_string()),
+            "encoder" if value.eq_ignore_ascii_case("steam") => kind = OtpKind::Steam,
+            _ => {}
+        }
+    }
+
+    // Gli URI di Steam sono TOTP normali con emittente Steam
+    if kind == OtpKind::Totp && issuer.is_some_and(|issuer| issuer.eq_ignore_ascii_case("steam")) {
+        kind = OtpKind::Steam;
+    }
+    if kind == OtpKind::Steam {
+        algorithm = OtpAlgorithm::Sha1;
+        digits = STEAM_DIGITS as u32;
+        period = DEFAULT_PERIOD;
+    } else if !matches!(digits, 6 | 8) {
+        return Err(format!("{} cifre non supportate (solo 6 o 8)", digits));
+    }
+    if period == 0 {
+        return Err("il periodo deve essere maggiore di zero".to_string());
+    }
+    if kind == OtpKind::Hotp && counter.is_none() {
+        return Err("contatore mancante nell'URI HOTP".to_string());
+    }
+
+    Ok(OtpUri {
+        secret: secret.ok_or("segreto mancante nell'URI")?,
+        kind,
+        algorithm,
+        digits,
+        period,
+        counter: counter.unwrap_or(0),
+    })
+}
+
+fn hmac_digest(algorithm: OtpAlgorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+    // HMAC accetta chiavi di qualsiasi lunghezza, new_from_slice non può fallire
+    match algorithm {
+        OtpAlgorithm::Sha1 => {
+            let mut mac = Hmac::<sha1::Sha1>::new_from_slice(key).expect("HMAC key");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        OtpAlgorithm::Sha256 => {
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC key");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        OtpAlgorithm::Sha512 => {
+            let mut mac = Hmac::<sha2::Sha512>::new_from_slice(key).expect("HMAC key");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+// Troncamento dinamico RFC 4226: 31 bit presi dall'offset indicato dall'ultimo byte
+fn hotp_value(key: &[u8], counter: u64, algorithm: OtpAlgorithm) -> u32 {
+    let digest = hmac_digest(algorithm, key, &counter.to_be_bytes());
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff
+}
+
+// Le cifre arrivano dal file del vault: 10^10 non sta in un u32, si accettano solo i valori standard
+fn supported_digits(digits: u32) -> Result<u32, String> {
+    if matches!(digits, 6 | 8) {
+        Ok(digits)
+    } else {
+        Err(format!("{} cifre non supportate (solo 6 o 8)", digits))
+    }
+}
+
+fn hotp(key: &[u8], counter: u64, algorithm: OtpAlgorithm, digits: u32) -> String {
+    let code = hotp_value(key, counter, algorithm) % 10u32.pow(digits);
+    format!("{:0width$}", code, width = digits as usize)
+}
+
+fn totp(key: &[u8], timestamp: u64, algorithm: OtpAlgorithm, digits: u32, period: u64) -> String {
+    hotp(key, timestamp / period, algorithm, digits)
+}
+
+pub fn steam_code(key: &[u8], timestamp: u64) -> String {
+    let mut value = hotp_value(key, timestamp / DEFAULT_PERIOD, OtpAlgorithm::Sha1);
+    let mut code = String::with_capacity(STEAM_DIGITS);
+    for _ in 0..STEAM_DIGITS {
+        code.push(STEAM_ALPHABET[value as usize % STEAM_ALPHABET.len()] as char);
+        value /= STEAM_ALPHABET.len() as u32;
+    }
+    code
+}
+
+pub fn seconds_remaining(timestamp: u64, period: u64) -> u64 {
+    period - timestamp % period
+}
+
+// Codice attuale di un'entry a tempo e secondi prima che cambi
+pub fn current_code(
+    entry: &PasswordEntry,
+    otp: &OtpSecret,
+    key: &[u8; 32],
+    vault_id: &str,
+    timestamp: u64,
+) -> Result<(String, u64), String> {
+    let secret = decrypt_otp_secret(entry, otp, key, vault_id)?;
+    let bytes = decode_base32(&secret)?;
+    match otp.kind {
+        OtpKind::Totp => {
+            let digits = supported_digits(otp.digits)?;
+            let period = otp.period.max(1);
+            Ok((
+                totp(&bytes, timestamp, otp.algorithm, digits, period),
+                seconds_remaining(timestamp, period),
+            ))
+        }
+        OtpKind::Steam => Ok((steam_code(&bytes, timestamp), seconds_remaining(timestamp, DEFAULT_PERIOD))),
+        OtpKind::Hotp => Err("i codici HOTP vanno generati dal contatore".to_string()),
+    }
+}
+
+// Codice HOTP per il contatore attuale; il chiamante deve salvare il contatore incrementato
+pub fn counter_code(entry: &PasswordEntry, otp: &OtpSecret, key: &[u8; 32], vault_id: &str) -> Result<String, String> {
+    let secret = decrypt_otp_secret(entry, otp, key, vault_id)?;
+    let bytes = decode_base32(&secret)?;
+    let digits = supported_digits(otp.digits)?;
+    Ok(hotp(&bytes, otp.counter, otp.algorithm, digits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Appendice B della RFC 6238: la chiave è ripetuta fino alla lunghezza dell'hash
+    const SHA1_KEY: &[u8] = b"12345678901234567890";
+    const SHA256_KEY: &[u8] = b"12345678901234567890123456789012";
+    const SHA512_KEY: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    const VECTORS: [(u64, &str, &str, &str); 6] = [
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    #[test]
+    fn rfc6238_vectors() {
+        for (time, sha1, sha256, sha512) in VECTORS {
+            assert_eq!(totp(SHA1_KEY, time, OtpAlgorithm::Sha1, 8, 30), sha1, "SHA1 at {}", time);
+            assert_eq!(totp(SHA256_KEY, time, OtpAlgorithm::Sha256, 8, 30), sha256, "SHA256 at {}", time);
+            assert_eq!(totp(SHA512_KEY, time, OtpAlgorithm::Sha512, 8, 30), sha512, "SHA512 at {}", time);
+        }
+    }
+
+    #[test]
+    fn six_digits_and_custom_period() {
+        assert_eq!(totp(SHA1_KEY, 59, OtpAlgorithm::Sha1, 6, 30), "287082");
+        // Con un periodo diverso cambia solo il contatore
+        assert_eq!(
+            totp(SHA1_KEY, 1111111109, OtpAlgorithm::Sha1, 8, 60),
+            hotp(SHA1_KEY, 1111111109 / 60, OtpAlgorithm::Sha1, 8)
+        );
+        assert_eq!(seconds_remaining(59, 30), 1);
+        assert_eq!(seconds_remaining(60, 60), 60);
+    }
+
+    // Appendice D della RFC 4226
+    #[test]
+    fn rfc4226_vectors() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489",
+        ];
+        for (counter, code) in expected.into_iter().enumerate() {
+            assert_eq!(hotp(SHA1_KEY, counter as u64, OtpAlgorithm::Sha1, 6), code, "counter {}", counter);
+        }
+    }
+
+    #[test]
+    fn unsupported_digits() {
+        assert_eq!(supported_digits(6), Ok(6));
+        assert_eq!(supported_digits(8), Ok(8));
+        for digits in [0, 7, 10, u32::MAX] {
+            assert!(supported_digits(digits).is_err(), "{} cifre", digits);
+        }
+    }
+
+    #[test]
+    fn steam_codes() {
+        // Valori calcolati con l'algoritmo di steam-totp (DoctorMcKay): a 59 s il contatore è 1 e la
+        // RFC 4226 dà 1094287082 come valore troncato, che in base 26 diventa PV9M4
+        let expected = [(59, "PV9M4"), (1111111109, "PY4YB"), (1234567890, "VHHQY"), (2000000000, "9N776")];
+        for (time, steam) in expected {
+            assert_eq!(steam_code(SHA1_KEY, time), steam, "Steam at {}", time);
+        }
+
+        let code = steam_code(SHA1_KEY, 59);
+        assert_eq!(code.len(), STEAM_DIGITS);
+        assert!(code.bytes().all(|c| STEAM_ALPHABET.contains(&c)));
+        // Stesso intervallo di 30 secondi, stesso codice
+        assert_eq!(steam_code(SHA1_KEY, 31), code);
+        assert_ne!(steam_code(SHA1_KEY, 60), code);
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        // "12345678901234567890" in Base32
+        let encoded = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        assert_eq!(&decode_base32(encoded).unwrap()[..], SHA1_KEY);
+        assert_eq!(&decode_base32("gezd gnbv-gy3t qojq gezd gnbv gy3t qojq").unwrap()[..], SHA1_KEY);
+        assert!(decode_base32("GEZ1").is_err());
+        assert!(decode_base32("").is_err());
+    }
+
+    #[test]
+    fn otpauth_uri() {
+        let uri = parse_otpauth_uri(
+            "otpauth://totp/Esempio:mario@example.com?secret=gezdgnbvgy3tqojq&issuer=Esempio&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(uri.secret.as_str(), "GEZDGNBVGY3TQOJQ");
+        assert_eq!(uri.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(uri.digits, 8);
+        assert_eq!(uri.period, 60);
+
+        let defaults = parse_otpauth_uri("otpauth://totp/label?secret=GEZDGNBV").unwrap();
+        assert_eq!(defaults.algorithm, OtpAlgorithm::Sha1);
+        assert_eq!((defaults.digits, defaults.period), (DEFAULT_DIGITS, DEFAULT_PERIOD));
+
+        let hotp = parse_otpauth_uri("otpauth://hotp/label?secret=GEZDGNBV&counter=42").unwrap();
+        assert_eq!((hotp.kind, hotp.counter), (OtpKind::Hotp, 42));
+        assert!(parse_otpauth_uri("otpauth://hotp/label?secret=GEZDGNBV").is_err());
+
+        let steam = parse_otpauth_uri("otpauth://totp/Steam:mario?secret=GEZDGNBV&issuer=Steam&digits=6").unwrap();
+        assert_eq!((steam.kind, steam.digits), (OtpKind::Steam, STEAM_DIGITS as u32));
+        assert_eq!(parse_otpauth_uri("steam://GEZDGNBV").unwrap().kind, OtpKind::Steam);
+
+        assert!(parse_otpauth_uri("otpauth://totp/label?issuer=x").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/label?secret=GEZDGNBV&digits=7").is_err());
+        assert!(parse_otpauth_uri("https://example.com").is_err());
+    }
+}
@@ -1,50 +1,841 @@
 use aes_gcm::{
     Aes256Gcm, Key, Nonce,
-    aead::{Aead, AeadCore, KeyInit, OsRng},
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHasher, PasswordVerifier, Version,
+    password_hash::{PasswordHash, SaltString, rand_core::OsRng as SaltRng},
 };
-use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
 use base64::Engine;
+use crate::helpers::generate_password::GeneratorOptions;
+use crate::helpers::otp::{DEFAULT_DIGITS, DEFAULT_PERIOD, OtpAlgorithm, OtpKind, normalize_secret, parse_otpauth_uri};
 use rand::Rng;
 use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs;
-use std::path::PathBuf;
+use subtle::ConstantTimeEq;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zeroize::{Zeroize, Zeroizing};
+
+// Parametri Argon2 per derivare la chiave, salvati nell'intestazione del vault
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    pub m_cost: u32, // KiB
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: "argon2id".to_string(),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    pub const ALGORITHMS: [&str; 3] = ["argon2id", "argon2i", "argon2d"];
+    // Limiti delle impostazioni: lo sblocco gira sul thread dell'interfaccia, che resta bloccato
+    // per tutto il calcolo (e con troppa memoria l'allocazione può fallire)
+    pub const MAX_MEMORY_MIB: u32 = 1024;
+    pub const MAX_ITERATIONS: u32 = 10;
+    pub const MAX_PARALLELISM: u32 = 8;
+
+    pub fn validate(&self) -> Result<(), String> {
+        let defaults = KdfParams::default();
+        if !(defaults.m_cost..=Self::MAX_MEMORY_MIB * 1024).contains(&self.m_cost) {
+            return Err(format!("La memoria deve essere tra {} e {} MiB", defaults.m_cost / 1024, Self::MAX_MEMORY_MIB));
+        }
+        if !(defaults.t_cost..=Self::MAX_ITERATIONS).contains(&self.t_cost) {
+            return Err(format!("Le iterazioni devono essere tra {} e {}", defaults.t_cost, Self::MAX_ITERATIONS));
+        }
+        if !(defaults.p_cost..=Self::MAX_PARALLELISM).contains(&self.p_cost) {
+            return Err(format!("Il parallelismo deve essere tra {} e {}", defaults.p_cost, Self::MAX_PARALLELISM));
+        }
+        Ok(())
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, String> {
+        let algorithm = match self.algorithm.as_str() {
+            "argon2id" => Algorithm::Argon2id,
+            "argon2i" => Algorithm::Argon2i,
+            "argon2d" => Algorithm::Argon2d,
+            other => return Err(format!("Unknown KDF algorithm: {}", other)),
+        };
+        self.argon2_with(algorithm)
+    }
+
+    // L'hash di verifica del login è sempre Argon2id: la variante scelta vale solo per la chiave
+    fn verifier(&self) -> Result<Argon2<'static>, String> {
+        self.argon2_with(Algorithm::Argon2id)
+    }
+
+    fn argon2_with(&self, algorithm: Algorithm) -> Result<Argon2<'static>, String> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| format!("Invalid KDF params: {:?}", e))?;
+        Ok(Argon2::new(algorithm, Version::V0x13, params))
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserData {
     pub u: String,   // Username
-    pub p_h: String, // Password hash
-    pub salt: String,
+    #[serde(default)]
+    pub kdf: KdfParams,
+    pub p_h: String, // Password hash (stringa PHC Argon2id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub salt: String, // Solo per i vecchi hash SHA-256
     pub key_salt: String,
+    #[serde(default)]
+    pub wrapped_keys: Vec<WrappedKey>,
+    #[serde(default)]
+    pub vault_id: String,
 }
 
+pub const UNLOCK_PASSWORD: &str = "password";
+
+// Chiave del vault cifrata con la chiave di un metodo di sblocco (per ora solo la master password)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub method: String,
+    pub key: String,
+    pub nonce: String,
+}
+
+// Tipo di entry: i login usano username e password, gli altri tipi i propri campi
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Login,
+    SecureNote,
+    Card,
+    Identity,
+    SshKey,
+    ApiToken,
+}
+
+pub struct TypedField {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub secret: bool,
+    pub multiline: bool,
+}
+
+const fn typed_field(key: &'static str, label: &'static str, secret: bool, multiline: bool) -> TypedField {
+    TypedField { key, label, secret, multiline }
+}
+
+const NOTE_FIELDS: &[TypedField] = &[typed_field("content", "Contenuto", true, true)];
+const CARD_FIELDS: &[TypedField] = &[
+    typed_field("number", "Numero carta", true, false),
+    typed_field("expiry", "Scadenza (MM/AA)", true, false),
+    typed_field("cvv", "CVV", true, false),
+    typed_field("holder", "Intestatario", true, false),
+];
+const IDENTITY_FIELDS: &[TypedField] = &[
+    typed_field("full_name", "Nome completo", true, false),
+    typed_field("address", "Indirizzo", true, true),
+    typed_field("phone", "Telefono", true, false),
+    typed_field("email", "Email", true, false),
+];
+const SSH_KEY_FIELDS: &[TypedField] = &[
+    typed_field("private_key", "Chiave privata", true, true),
+    typed_field("public_key", "Chiave pubblica", false, true),
+    typed_field("passphrase", "Passphrase", true, false),
+];
+const API_TOKEN_FIELDS: &[TypedField] = &[
+    typed_field("token", "Token", true, false),
+    typed_field("endpoint", "Endpoint", false, false),
+    typed_field("expires", "Scadenza", false, false),
+];
+
+impl EntryKind {
+    pub const ALL: [EntryKind; 6] = [
+        EntryKind::Login,
+        EntryKind::SecureNote,
+        EntryKind::Card,
+        EntryKind::Identity,
+        EntryKind::SshKey,
+        EntryKind::ApiToken,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryKind::Login => "🔑 Login",
+            EntryKind::SecureNote => "📝 Nota sicura",
+            EntryKind::Card => "💳 Carta di pagamento",
+            EntryKind::Identity => "🪪 Identità",
+            EntryKind::SshKey => "🖥 Chiave SSH",
+            EntryKind::ApiToken => "🧾 Token API",
+        }
+    }
+
+    pub fn fields(self) -> &'static [TypedField] {
+        match self {
+            EntryKind::Login => &[],
+            EntryKind::SecureNote => NOTE_FIELDS,
+            EntryKind::Card => CARD_FIELDS,
+            EntryKind::Identity => IDENTITY_FIELDS,
+            EntryKind::SshKey => SSH_KEY_FIELDS,
+            EntryKind::ApiToken => API_TOKEN_FIELDS,
+        }
+    }
+
+    // Campo copiato dal pulsante 📋 della lista
+    pub fn primary_field(self) -> Option<&'static TypedField> {
+        self.fields().first()
+    }
+
+    pub fn has_password(self) -> bool {
+        self == EntryKind::Login
+    }
+
+    fn is_login(&self) -> bool {
+        *self == EntryKind::Login
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PasswordEntry {
+    #[serde(default)]
+    pub id: String, // Vuoto solo nei vault creati prima degli ID, viene assegnato al login
+    #[serde(default, skip_serializing_if = "EntryKind::is_login")]
+    pub kind: EntryKind,
     pub name: String,
     pub u: String,   // Username
     pub e_c: String, // Password crypt
     pub nonce: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PreviousPassword>, // Dalla più recente alla più vecchia
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<CustomField>,
+    // Campi del tipo di entry (id = chiave del campo), cifrati come quelli personalizzati
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub data: Vec<CustomField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>, // ID della cartella, None = nessuna cartella
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator_profile: Option<String>, // Profilo usato da "Genera" nel pannello di modifica
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<OtpSecret>,
+    // Secondi dall'epoch Unix; assenti nelle entry create prima che venissero registrati
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+}
+
+// Il contenuto è cifrato in un file a parte, nella cartella attachments accanto a data.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub size: u64,     // Byte
+    pub added_at: u64, // Secondi dall'epoch Unix
+}
+
+// Segreto OTP in Base32, cifrato e legato all'entry come i campi segreti
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpSecret {
+    #[serde(default, skip_serializing_if = "OtpKind::is_totp")]
+    pub kind: OtpKind,
+    pub secret: String,
+    pub nonce: String,
+    #[serde(default)]
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64, // Secondi, solo TOTP
+    #[serde(default)]
+    pub counter: u64, // Prossimo contatore HOTP
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomField {
+    pub id: String,
+    pub name: String,
+    pub value: String, // Cifrato come la password se il campo è segreto
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub nonce: String,
+    #[serde(default)]
+    pub secret: bool,
+}
+
+// Password sostituita: resta cifrata e legata alla sua entry come quella attuale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviousPassword {
+    pub e_c: String,
+    pub nonce: String,
+    pub replaced_at: u64, // Secondi dall'epoch Unix
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl PasswordEntry {
+    // Sostituisce la password cifrata, conservando la precedente nella cronologia
+    pub fn replace_password(&mut self, e_c: String, nonce: String, depth: usize) {
+        let previous = PreviousPassword {
+            e_c: std::mem::replace(&mut self.e_c, e_c),
+            nonce: std::mem::replace(&mut self.nonce, nonce),
+            replaced_at: now_timestamp(),
+        };
+        self.history.insert(0, previous);
+        self.history.truncate(depth);
+    }
+
+    // I campi segreti vengono cercati solo per nome
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let contains = |text: &str| text.to_lowercase().contains(&query);
+
+        contains(&self.name)
+            || contains(&self.u)
+            || contains(&self.notes)
+            || self.urls.iter().any(|url| contains(url))
+            || self.tags.iter().any(|tag| contains(tag))
+            || self
+                .fields
+                .iter()
+                .any(|field| contains(&field.name) || (!field.secret && contains(&field.value)))
+            || self.data.iter().any(|field| !field.secret && contains(&field.value))
+    }
+
+    pub fn data_field(&self, key: &str) -> Option<&CustomField> {
+        self.data.iter().find(|field| field.id == key)
+    }
+
+    // Rimette in uso una password della cronologia; quella attuale finisce nella cronologia
+    pub fn restore_previous(&mut self, index: usize, depth: usize) -> bool {
+        if index >= self.history.len() {
+            return false;
+        }
+        let restored = self.history.remove(index);
+        self.replace_password(restored.e_c, restored.nonce, depth.max(1));
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppData {
     pub user: Option<UserData>,
     pub ps: Vec<PasswordEntry>, // Password salvate
     pub dark_mode: Option<bool>,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub trash: Vec<TrashedEntry>, // Password eliminate, cifrate come le altre
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+    #[serde(default)]
+    pub profiles: Vec<GeneratorProfile>,
+}
+
+// Regole di generazione con un nome, da associare alle entry dei siti con requisiti particolari
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorProfile {
+    pub id: String,
+    pub name: String,
+    pub options: GeneratorOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    pub parent: Option<String>, // None = cartella di primo livello
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    pub entry: PasswordEntry,
+    pub deleted_at: u64, // Secondi dall'epoch Unix
+}
+
+impl AppData {
+    pub fn vault_id(&self) -> &str {
+        self.user.as_ref().map_or("", |user| &user.vault_id)
+    }
+
+    pub fn entry(&self, id: &str) -> Option<&PasswordEntry> {
+        self.ps.iter().find(|entry| entry.id == id)
+    }
+
+    pub fn entry_mut(&mut self, id: &str) -> Option<&mut PasswordEntry> {
+        self.ps.iter_mut().find(|entry| entry.id == id)
+    }
+
+    // Cerca anche nel percorso della cartella dell'entry
+    pub fn entry_matches(&self, entry: &PasswordEntry, query: &str) -> bool {
+        entry.matches(query)
+            || entry.folder.as_deref().is_some_and(|folder| {
+                self.folder_path(folder).to_lowercase().contains(&query.to_lowercase())
+            })
+    }
+
+    pub fn entries_named(&self, name: &str) -> impl Iterator<Item = &PasswordEntry> {
+        let name = name.trim().to_lowercase();
+        self.ps.iter().filter(move |entry| entry.name.trim().to_lowercase() == name)
+    }
+
+    // Sposta le entry nel cestino; restituisce i nomi di quelle trovate
+    pub fn trash_entries(&mut self, ids: &[String]) -> Vec<String> {
+        let deleted_at = now_timestamp();
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.ps)
+            .into_iter()
+            .partition(|entry| ids.contains(&entry.id));
+        self.ps = kept;
+
+        let names = removed.iter().map(|entry| entry.name.clone()).collect();
+        self.trash.extend(removed.into_iter().map(|entry| TrashedEntry { entry, deleted_at }));
+        names
+    }
+
+    pub fn folder(&self, id: &str) -> Option<&Folder> {
+        self.folders.iter().find(|folder| folder.id == id)
+    }
+
+    pub fn child_folders(&self, parent: Option<&str>) -> Vec<&Folder> {
+        let mut children: Vec<&Folder> = self
+            .folders
+            .iter()
+            .filter(|folder| folder.parent.as_deref() == parent)
+            .collect();
+        children.sort_by_key(|folder| folder.name.to_lowercase());
+        children
+    }
+
+    // Percorso completo, es. "Lavoro / Clienti"
+    pub fn folder_path(&self, id: &str) -> String {
+        let mut names = Vec::new();
+        let mut current = self.folder(id);
+        while let Some(folder) = current {
+            // Un file modificato a mano potrebbe contenere un ciclo
+            if names.len() > self.folders.len() {
+                break;
+            }
+            names.push(folder.name.as_str());
+            current = folder.parent.as_deref().and_then(|parent| self.folder(parent));
+        }
+        names.reverse();
+        names.join(" / ")
+    }
+
+    // La cartella e tutte le sue sottocartelle
+    pub fn folder_subtree(&self, id: &str) -> Vec<String> {
+        let mut subtree = vec![id.to_string()];
+        let mut index = 0;
+        while index < subtree.len() {
+            for folder in &self.folders {
+                if folder.parent.as_ref() == Some(&subtree[index]) && !subtree.contains(&folder.id) {
+                    subtree.push(folder.id.clone());
+                }
+            }
+            index += 1;
+        }
+        subtree
+    }
+
+    // Sottocartelle e password passano alla cartella superiore
+    pub fn delete_folder(&mut self, id: &str) -> Option<String> {
+        let position = self.folders.iter().position(|folder| folder.id == id)?;
+        let removed = self.folders.remove(position);
+
+        for folder in &mut self.folders {
+            if folder.parent.as_deref() == Some(id) {
+                folder.parent = removed.parent.clone();
+            }
+        }
+        let entries = self.ps.iter_mut().chain(self.trash.iter_mut().map(|trashed| &mut trashed.entry));
+        for entry in entries {
+            if entry.folder.as_deref() == Some(id) {
+                entry.folder = removed.parent.clone();
+            }
+        }
+        Some(removed.name)
+    }
+
+    pub fn profile(&self, id: &str) -> Option<&GeneratorProfile> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    // Le entry che lo usavano tornano alle opzioni del generatore
+    pub fn delete_profile(&mut self, id: &str) -> Option<String> {
+        let position = self.profiles.iter().position(|profile| profile.id == id)?;
+        let removed = self.profiles.remove(position);
+
+        let entries = self.ps.iter_mut().chain(self.trash.iter_mut().map(|trashed| &mut trashed.entry));
+        for entry in entries {
+            if entry.generator_profile.as_deref() == Some(id) {
+                entry.generator_profile = None;
+            }
+        }
+        Some(removed.name)
+    }
+
+    // Cifra i campi di tipo salvati in chiaro che ora sono segreti (es. i dati delle identità).
+    // Restituisce se qualcosa è cambiato
+    pub fn seal_typed_fields(&mut self, key: &[u8; 32], vault_id: &str) -> Result<bool, String> {
+        let mut changed = false;
+        let trashed = self.trash.iter_mut().map(|trashed| &mut trashed.entry);
+        for entry in self.ps.iter_mut().chain(trashed) {
+            for spec in entry.kind.fields().iter().filter(|spec| spec.secret) {
+                let entry_id = entry.id.clone();
+                let Some(field) = entry.data.iter_mut().find(|field| field.id == spec.key && !field.secret) else {
+                    continue;
+                };
+                let (value, nonce) = encrypt_bytes(field.value.as_bytes(), &field_aad(vault_id, &entry_id, &field.id), key)?;
+                field.value.zeroize();
+                field.value = value;
+                field.nonce = nonce;
+                field.secret = true;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn trim_history(&mut self) {
+        let depth = self.settings.history_depth;
+        // Anche le entry nel cestino: altrimenti le vecchie password resterebbero finché non viene svuotato
+        let trashed = self.trash.iter_mut().map(|trashed| &mut trashed.entry);
+        for entry in self.ps.iter_mut().chain(trashed) {
+            entry.history.truncate(depth);
+        }
+    }
+
+    pub fn restore_from_trash(&mut self, id: &str) -> Option<String> {
+        let position = self.trash.iter().position(|trashed| trashed.entry.id == id)?;
+        let mut entry = self.trash.remove(position).entry;
+        if entry.folder.as_deref().is_some_and(|folder| self.folder(folder).is_none()) {
+            entry.folder = None;
+        }
+        let name = entry.name.clone();
+        self.ps.push(entry);
+        Some(name)
+    }
+
+    // Elimina definitivamente le entry rimaste nel cestino oltre il periodo di conservazione
+    pub fn purge_expired_trash(&mut self) -> usize {
+        let retention_days = self.settings.trash_retention_days;
+        if retention_days == 0 {
+            return 0;
+        }
+
+        let cutoff = now_timestamp().saturating_sub(u64::from(retention_days) * 24 * 60 * 60);
+        let before = self.trash.len();
+        self.trash.retain(|trashed| trashed.deleted_at > cutoff);
+        before - self.trash.len()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub backup_count: usize, // Copie data.json.bak.N da mantenere
+    pub trash_retention_days: u32, // 0 = il cestino non viene mai svuotato da solo
+    pub history_depth: usize, // Password precedenti da conservare per ogni entry
+    pub attachment_limit_mb: u64, // Dimensione massima di ogni allegato
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            backup_count: 3,
+            trash_retention_days: 30,
+            history_depth: 5,
+            attachment_limit_mb: 10,
+        }
+    }
+}
+
+// Campi aggiuntivi dei form di aggiunta e modifica, in chiaro finché non vengono salvati
+#[derive(Default)]
+pub struct EntryExtras {
+    pub urls: String, // Uno per riga
+    pub notes: String,
+    pub tags: String, // Separati da virgola
+    pub fields: Vec<FieldDraft>,
+    pub folder: Option<String>,
+    pub generator_profile: Option<String>,
+    pub kind: EntryKind,
+    pub typed: Vec<FieldDraft>, // Uno per ogni campo di kind.fields()
+    pub otp: OtpDraft,
+}
+
+// Segreto vuoto = nessun codice OTP
+pub struct OtpDraft {
+    pub secret: SecretString, // Base32 oppure URI otpauth://
+    pub kind: OtpKind,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub counter: u64,
+    loaded_counter: u64, // Per capire se il contatore è stato cambiato a mano
+}
+
+impl Default for OtpDraft {
+    fn default() -> Self {
+        Self {
+            secret: secret_string(),
+            kind: OtpKind::default(),
+            algorithm: OtpAlgorithm::default(),
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+            counter: 0,
+            loaded_counter: 0,
+        }
+    }
+}
+
+impl OtpDraft {
+    // Se è stato incollato un URI otpauth://, ne prende segreto e parametri
+    pub fn import_uri(&mut self) -> Result<bool, String> {
+        if !self.secret.trim_start().starts_with("otpauth://") {
+            return Ok(false);
+        }
+        let uri = parse_otpauth_uri(&self.secret)?;
+        self.secret.clear();
+        self.secret.push_str(&uri.secret);
+        self.kind = uri.kind;
+        self.algorithm = uri.algorithm;
+        self.digits = uri.digits;
+        self.period = uri.period;
+        self.counter = uri.counter;
+        Ok(true)
+    }
+
+    // Un codice HOTP generato mentre il form era aperto ha già fatto avanzare il contatore:
+    // si tiene quello salvato, a meno che non sia stato modificato nel form
+    fn seal(&self, current: Option<&OtpSecret>, entry_id: &str, key: &[u8; 32], vault_id: &str) -> Result<Option<OtpSecret>, String> {
+        if self.secret.trim().is_empty() {
+            return Ok(None);
+        }
+        let mut draft = OtpDraft {
+            secret: Zeroizing::new(self.secret.to_string()),
+            ..*self
+        };
+        let imported = draft.import_uri()?;
+        let counter = match current {
+            Some(current) if !imported && draft.counter == draft.loaded_counter => current.counter,
+            _ => draft.counter,
+        };
+        let normalized = normalize_secret(&draft.secret).map_err(|e| format!("Segreto TOTP non valido: {}", e))?;
+        let (secret, nonce) = encrypt_bytes(normalized.as_bytes(), &otp_aad(vault_id, entry_id), key)?;
+        Ok(Some(OtpSecret {
+            kind: draft.kind,
+            secret,
+            nonce,
+            algorithm: draft.algorithm,
+            // Un segreto Steam importato e poi cambiato di tipo avrebbe ancora 5 cifre
+            digits: if matches!(draft.digits, 6 | 8) { draft.digits } else { DEFAULT_DIGITS },
+            period: draft.period.max(1),
+            counter,
+        }))
+    }
+}
+
+pub struct FieldDraft {
+    pub id: String,
+    pub name: String,
+    pub value: SecretString,
+    pub secret: bool,
+}
+
+impl FieldDraft {
+    pub fn new() -> Self {
+        Self {
+            id: generate_id(),
+            name: String::new(),
+            value: secret_string(),
+            secret: false,
+        }
+    }
+
+    fn seal(&self, entry_id: &str, key: &[u8; 32], vault_id: &str) -> Result<CustomField, String> {
+        let (value, nonce) = if self.secret {
+            encrypt_bytes(self.value.as_bytes(), &field_aad(vault_id, entry_id, &self.id), key)?
+        } else {
+            (self.value.to_string(), String::new())
+        };
+        Ok(CustomField {
+            id: self.id.clone(),
+            name: self.name.trim().to_string(),
+            value,
+            nonce,
+            secret: self.secret,
+        })
+    }
 }
 
-fn get_data_file_path() -> PathBuf {
-    let home_dir = dirs::home_dir().expect("Unable to find home directory");
-    let app_dir = home_dir.join("p_manager");
+impl EntryExtras {
+    // Cambia tipo conservando i valori dei campi con la stessa chiave
+    pub fn set_kind(&mut self, kind: EntryKind) {
+        let mut previous = std::mem::take(&mut self.typed);
+        self.kind = kind;
+        self.typed = kind
+            .fields()
+            .iter()
+            .map(|field| {
+                let value = previous
+                    .iter_mut()
+                    .find(|draft| draft.id == field.key)
+                    .map_or_else(secret_string, |draft| std::mem::replace(&mut draft.value, secret_string()));
+                FieldDraft {
+                    id: field.key.to_string(),
+                    name: field.label.to_string(),
+                    value,
+                    secret: field.secret,
+                }
+            })
+            .collect();
+    }
+
+    pub fn from_entry(entry: &PasswordEntry, key: &[u8; 32], vault_id: &str) -> Result<Self, String> {
+        let mut fields = Vec::with_capacity(entry.fields.len());
+        for field in &entry.fields {
+            fields.push(FieldDraft {
+                id: field.id.clone(),
+                name: field.name.clone(),
+                value: decrypt_field(entry, field, key, vault_id)?,
+                secret: field.secret,
+            });
+        }
+
+        let mut typed = Vec::with_capacity(entry.kind.fields().len());
+        for field in entry.kind.fields() {
+            let value = match entry.data_field(field.key) {
+                Some(stored) => decrypt_field(entry, stored, key, vault_id)?,
+                None => secret_string(),
+            };
+            typed.push(FieldDraft {
+                id: field.key.to_string(),
+                name: field.label.to_string(),
+                value,
+                secret: field.secret,
+            });
+        }
+
+        let otp = match &entry.otp {
+            Some(otp) => OtpDraft {
+                secret: decrypt_otp_secret(entry, otp, key, vault_id)?,
+                kind: otp.kind,
+                algorithm: otp.algorithm,
+                digits: otp.digits,
+                period: otp.period,
+                counter: otp.counter,
+                loaded_counter: otp.counter,
+            },
+            None => OtpDraft::default(),
+        };
+
+        Ok(Self {
+            urls: entry.urls.join("\n"),
+            notes: entry.notes.clone(),
+            tags: entry.tags.join(", "),
+            fields,
+            folder: entry.folder.clone(),
+            generator_profile: entry.generator_profile.clone(),
+            kind: entry.kind,
+            typed,
+            otp,
+        })
+    }
+
+    // Scrive i campi nell'entry, cifrando quelli segreti con il suo ID
+    pub fn apply_to(&self, entry: &mut PasswordEntry, key: &[u8; 32], vault_id: &str) -> Result<(), String> {
+        let mut fields = Vec::with_capacity(self.fields.len());
+        for draft in self.fields.iter().filter(|draft| !draft.name.trim().is_empty()) {
+            fields.push(draft.seal(&entry.id, key, vault_id)?);
+        }
+
+        let mut data = Vec::with_capacity(self.typed.len());
+        for draft in self.typed.iter().filter(|draft| !draft.value.is_empty()) {
+            data.push(draft.seal(&entry.id, key, vault_id)?);
+        }
+
+        let otp = self.otp.seal(entry.otp.as_ref(), &entry.id, key, vault_id)?;
 
-    if !app_dir.exists() {
-        if let Err(_e) = fs::create_dir_all(&app_dir) {
-            return PathBuf::from("data.json");
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !tags.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
         }
+
+        entry.urls = self
+            .urls
+            .lines()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        entry.notes = self.notes.trim_end().to_string();
+        entry.tags = tags;
+        entry.fields = fields;
+        entry.folder = self.folder.clone();
+        entry.generator_profile = self.generator_profile.clone();
+        entry.kind = self.kind;
+        entry.data = data;
+        entry.otp = otp;
+        Ok(())
+    }
+}
+
+// Chiavi e testi in chiaro: la memoria viene azzerata quando vengono rilasciati
+pub type SecretKey = Zeroizing<[u8; 32]>;
+pub type SecretString = Zeroizing<String>;
+
+// I campi di input partono con spazio sufficiente: se la String dovesse riallocare,
+// il buffer precedente verrebbe liberato senza essere azzerato
+pub fn secret_string() -> SecretString {
+    Zeroizing::new(String::with_capacity(256))
+}
+
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// Tempo trascorso da un timestamp, in forma leggibile
+pub fn format_age(timestamp: u64) -> String {
+    let seconds = now_timestamp().saturating_sub(timestamp);
+    match seconds {
+        0..60 => "adesso".to_string(),
+        60..3600 => format!("{} min fa", seconds / 60),
+        3600..86400 => format!("{} ore fa", seconds / 3600),
+        _ => format!("{} giorni fa", seconds / 86400),
     }
+}
+
+pub fn generate_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
 
-    app_dir.join("data.json")
+// Dati associati di ogni password: il testo cifrato vale solo per la sua entry nel suo vault
+fn entry_aad(vault_id: &str, entry_id: &str) -> Vec<u8> {
+    format!("{}/{}", vault_id, entry_id).into_bytes()
+}
+
+// I campi segreti sono legati anche al proprio ID, per non poter essere scambiati tra loro
+fn field_aad(vault_id: &str, entry_id: &str, field_id: &str) -> Vec<u8> {
+    format!("{}/{}/{}", vault_id, entry_id, field_id).into_bytes()
+}
+
+fn otp_aad(vault_id: &str, entry_id: &str) -> Vec<u8> {
+    field_aad(vault_id, entry_id, "otp")
 }
 
 pub fn generate_salt() -> String {
@@ -53,7 +844,17 @@ pub fn generate_salt() -> String {
     base64::engine::general_purpose::STANDARD.encode(salt)
 }
 
-pub fn hash_password(password: &str, salt: &str) -> String {
+pub fn hash_password(password: &str, params: &KdfParams) -> Result<String, String> {
+    let salt = SaltString::generate(&mut SaltRng);
+    params
+        .verifier()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Hashing error: {:?}", e))
+}
+
+// Vecchio formato: SHA-256 di password + salt, rimasto solo per la migrazione
+fn legacy_hash_password(password: &str, salt: &str) -> String {
     let mut hasher = Sha256::default();
     hasher.update(password.as_bytes());
     hasher.update(salt.as_bytes());
@@ -61,103 +862,328 @@ pub fn hash_password(password: &str, salt: &str) -> String {
     base64::engine::general_purpose::STANDARD.encode(result)
 }
 
-pub fn derive_key(password: &str, salt: &str) -> [u8; 32] {
-    let argon2 = Argon2::default();
-    let salt_bytes = base64::engine::general_purpose::STANDARD
-        .decode(salt)
-        .unwrap_or_else(|_| salt.as_bytes().to_vec());
+pub fn is_legacy_hash(p_h: &str) -> bool {
+    !p_h.starts_with("$argon2")
+}
 
-    let mut fixed_salt = [0u8; 16];
-    if salt_bytes.len() >= 16 {
-        fixed_salt.copy_from_slice(&salt_bytes[..16]);
-    } else {
-        fixed_salt[..salt_bytes.len()].copy_from_slice(&salt_bytes);
+pub fn verify_password(password: &str, p_h: &str, salt: &str) -> bool {
+    if is_legacy_hash(p_h) {
+        let computed = legacy_hash_password(password, salt);
+        return computed.as_bytes().ct_eq(p_h.as_bytes()).into();
     }
 
-    let salt_string = SaltString::encode_b64(&fixed_salt).unwrap();
+    match PasswordHash::new(p_h) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
 
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt_string)
-        .unwrap();
-    let hash = password_hash.hash.unwrap();
+pub fn derive_key(password: &str, salt: &str, params: &KdfParams) -> Result<SecretKey, String> {
+    let salt_bytes = base64::engine::general_purpose::STANDARD
+        .decode(salt)
+        .unwrap_or_else(|_| salt.as_bytes().to_vec());
 
-    let mut key = [0u8; 32];
-    let hash_bytes = hash.as_bytes();
-    if hash_bytes.len() >= 32 {
-        key.copy_from_slice(&hash_bytes[..32]);
-    } else {
-        key[..hash_bytes.len()].copy_from_slice(hash_bytes);
-    }
-    key
+    let mut key = Zeroizing::new([0u8; 32]);
+    params
+        .argon2()?
+        .hash_password_into(password.as_bytes(), &salt_bytes, key.as_mut())
+        .map_err(|e| format!("Key derivation error: {:?}", e))?;
+    Ok(key)
 }
 
-pub fn encrypt_password(password: &str, key_bytes: &[u8; 32]) -> Result<(String, String), String> {
+// Tempo di sblocco con questi parametri su questa macchina: il login verifica l'hash
+// e poi deriva la chiave, quindi Argon2 gira due volte
+pub fn benchmark_kdf(params: &KdfParams) -> Result<Duration, String> {
+    params.validate()?;
+    let start = Instant::now();
+    hash_password("benchmark", params)?;
+    derive_key("benchmark", &generate_salt(), params)?;
+    Ok(start.elapsed())
+}
+
+pub fn encrypt_bytes(
+    plaintext: &[u8],
+    aad: &[u8],
+    key_bytes: &[u8; 32],
+) -> Result<(String, String), String> {
     let key = Key::<Aes256Gcm>::from_slice(key_bytes);
     let cipher = Aes256Gcm::new(key);
 
     let nonce_bytes = Aes256Gcm::generate_nonce(&mut OsRng);
     let ciphertext = cipher
-        .encrypt(&nonce_bytes, password.as_bytes())
+        .encrypt(&nonce_bytes, Payload { msg: plaintext, aad })
         .map_err(|e| format!("Encryption error: {:?}", e))?;
 
-    let encrypted_password = base64::engine::general_purpose::STANDARD.encode(ciphertext);
+    let ciphertext = base64::engine::general_purpose::STANDARD.encode(ciphertext);
     let nonce = base64::engine::general_purpose::STANDARD.encode(nonce_bytes);
 
-    Ok((encrypted_password, nonce))
+    Ok((ciphertext, nonce))
 }
 
-pub fn decrypt_password(entry: &PasswordEntry, key_bytes: &[u8; 32]) -> Result<String, String> {
+pub fn decrypt_bytes(
+    ciphertext: &str,
+    nonce: &str,
+    aad: &[u8],
+    key_bytes: &[u8; 32],
+) -> Result<Zeroizing<Vec<u8>>, String> {
     let key = Key::<Aes256Gcm>::from_slice(key_bytes);
     let cipher = Aes256Gcm::new(key);
 
     let ciphertext = base64::engine::general_purpose::STANDARD
-        .decode(&entry.e_c)
+        .decode(ciphertext)
         .map_err(|e| format!("Base64 decode error: {:?}", e))?;
     let nonce_bytes = base64::engine::general_purpose::STANDARD
-        .decode(&entry.nonce)
+        .decode(nonce)
         .map_err(|e| format!("Nonce decode error: {:?}", e))?;
+    if nonce_bytes.len() != 12 {
+        return Err("Nonce decode error: invalid length".to_string());
+    }
     let nonce = Nonce::from_slice(&nonce_bytes);
 
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext.as_ref())
-        .map_err(|e| format!("Decryption error: {:?}", e))?;
-    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion error: {:?}", e))
+    cipher
+        .decrypt(nonce, Payload { msg: &ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|e| format!("Decryption error: {:?}", e))
 }
 
-pub fn load_data() -> AppData {
-    let data_file = get_data_file_path();
+pub fn generate_vault_key() -> SecretKey {
+    let mut key = Zeroizing::new([0u8; 32]);
+    rand::rng().fill(key.as_mut());
+    key
+}
 
-    if data_file.exists() {
-        let data = fs::read_to_string(&data_file).unwrap_or_default();
-        serde_json::from_str(&data).unwrap_or_else(|_| AppData {
-            user: None,
-            ps: Vec::new(),
-            dark_mode: Some(true),
-        })
-    } else {
-        AppData {
-            user: None,
-            ps: Vec::new(),
-            dark_mode: Some(true),
-        }
+pub fn wrap_key(vault_key: &[u8; 32], method: &str, wrapping_key: &[u8; 32]) -> Result<WrappedKey, String> {
+    let (key, nonce) = encrypt_bytes(vault_key, b"", wrapping_key)?;
+    Ok(WrappedKey {
+        method: method.to_string(),
+        key,
+        nonce,
+    })
+}
+
+pub fn unwrap_key(wrapped: &WrappedKey, wrapping_key: &[u8; 32]) -> Result<SecretKey, String> {
+    let plaintext = decrypt_bytes(&wrapped.key, &wrapped.nonce, b"", wrapping_key)?;
+    if plaintext.len() != 32 {
+        return Err("Unwrap error: invalid key length".to_string());
+    }
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&plaintext);
+    Ok(key)
+}
+
+pub fn decrypt_field(
+    entry: &PasswordEntry,
+    field: &CustomField,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+) -> Result<SecretString, String> {
+    if !field.secret {
+        return Ok(Zeroizing::new(field.value.clone()));
     }
+    let plaintext = decrypt_bytes(&field.value, &field.nonce, &field_aad(vault_id, &entry.id, &field.id), key_bytes)?;
+    std::str::from_utf8(&plaintext)
+        .map(|value| Zeroizing::new(value.to_string()))
+        .map_err(|e| format!("UTF-8 conversion error: {:?}", e))
+}
+
+pub fn decrypt_otp_secret(
+    entry: &PasswordEntry,
+    otp: &OtpSecret,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+) -> Result<SecretString, String> {
+    let plaintext = decrypt_bytes(&otp.secret, &otp.nonce, &otp_aad(vault_id, &entry.id), key_bytes)?;
+    std::str::from_utf8(&plaintext)
+        .map(|secret| Zeroizing::new(secret.to_string()))
+        .map_err(|e| format!("UTF-8 conversion error: {:?}", e))
 }
 
-pub fn save_data(data: &AppData) {
-    let data_file = get_data_file_path();
+pub fn encrypt_password(
+    password: &str,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+    entry_id: &str,
+) -> Result<(String, String), String> {
+    encrypt_bytes(password.as_bytes(), &entry_aad(vault_id, entry_id), key_bytes)
+}
 
-    if let Ok(json) = serde_json::to_string_pretty(data) {
-        let _ = fs::write(data_file, json);
+// Fallisce anche se e_c/nonce sono stati spostati da un'altra entry o da un altro vault
+pub fn decrypt_password(
+    entry: &PasswordEntry,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+) -> Result<SecretString, String> {
+    decrypt_entry_ciphertext(&entry.e_c, &entry.nonce, key_bytes, vault_id, &entry.id)
+}
+
+pub fn decrypt_previous_password(
+    entry: &PasswordEntry,
+    previous: &PreviousPassword,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+) -> Result<SecretString, String> {
+    decrypt_entry_ciphertext(&previous.e_c, &previous.nonce, key_bytes, vault_id, &entry.id)
+}
+
+fn decrypt_entry_ciphertext(
+    e_c: &str,
+    nonce: &str,
+    key_bytes: &[u8; 32],
+    vault_id: &str,
+    entry_id: &str,
+) -> Result<SecretString, String> {
+    let aad = entry_aad(vault_id, entry_id);
+    let plaintext = decrypt_bytes(e_c, nonce, &aad, key_bytes)?;
+    std::str::from_utf8(&plaintext)
+        .map(|password| Zeroizing::new(password.to_string()))
+        .map_err(|e| format!("UTF-8 conversion error: {:?}", e))
+}
+
+// Assegna un ID alle entry che non lo hanno e le ricifra legandole a ID e vault.
+// Restituisce true se qualcosa è cambiato
+pub fn bind_legacy_entries(
+    ps: &mut [PasswordEntry],
+    key: &[u8; 32],
+    vault_id: &str,
+) -> Result<bool, String> {
+    let mut bound = Vec::new();
+    for (index, entry) in ps.iter().enumerate().filter(|(_, entry)| entry.id.is_empty()) {
+        let plaintext = decrypt_bytes(&entry.e_c, &entry.nonce, b"", key)
+            .map_err(|e| format!("'{}': {}", entry.name, e))?;
+        let password = std::str::from_utf8(&plaintext)
+            .map_err(|e| format!("'{}': {:?}", entry.name, e))?;
+
+        let id = generate_id();
+        let (e_c, nonce) = encrypt_password(password, key, vault_id, &id)?;
+        bound.push((index, id, e_c, nonce));
+    }
+
+    let changed = !bound.is_empty();
+    for (index, id, e_c, nonce) in bound {
+        let entry = &mut ps[index];
+        entry.id = id;
+        entry.e_c = e_c;
+        entry.nonce = nonce;
+    }
+    Ok(changed)
+}
+
+// Ricifra le password con una nuova chiave, tutte o nessuna
+pub fn reencrypt_entries(
+    ps: &mut [PasswordEntry],
+    old_key: &[u8; 32],
+    new_key: &[u8; 32],
+    vault_id: &str,
+) -> Result<(), String> {
+    let mut reencrypted = Vec::with_capacity(ps.len());
+    for entry in ps.iter() {
+        let password = decrypt_password(entry, old_key, vault_id)
+            .map_err(|e| format!("'{}': {}", entry.name, e))?;
+        reencrypted.push(encrypt_password(&password, new_key, vault_id, &entry.id)?);
     }
+
+    for (entry, (e_c, nonce)) in ps.iter_mut().zip(reencrypted) {
+        entry.e_c = e_c;
+        entry.nonce = nonce;
+    }
+    Ok(())
 }
 
 pub fn confirm_notification() -> bool {
+    confirm_dialog(
+        "Conferma Eliminazione",
+        "Sei sicuro di voler eliminare definitivamente questa password? Questa azione non può essere annullata.",
+    )
+}
+
+pub fn confirm_dialog(title: &str, description: &str) -> bool {
     let result = MessageDialog::new()
         .set_level(MessageLevel::Warning)
-        .set_title("Conferma Eliminazione")
-        .set_description("Sei sicuro di voler eliminare questa password? Questa azione non può essere annullata.")
+        .set_title(title)
+        .set_description(description)
         .set_buttons(MessageButtons::YesNo)
         .show();
 
     result == MessageDialogResult::Yes
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed_entry(password: &str, key: &[u8; 32], vault_id: &str) -> PasswordEntry {
+        let id = generate_id();
+        let (e_c, nonce) = encrypt_password(password, key, vault_id, &id).unwrap();
+        PasswordEntry {
+            id,
+            e_c,
+            nonce,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn verifier_is_always_argon2id() {
+        for algorithm in KdfParams::ALGORITHMS {
+            let params = KdfParams {
+                algorithm: algorithm.to_string(),
+                m_cost: 64,
+                t_cost: 1,
+                p_cost: 1,
+            };
+            let p_h = hash_password("segreta", &params).unwrap();
+            assert!(p_h.starts_with("$argon2id$v=19$m=64,t=1,p=1$"), "{}: {}", algorithm, p_h);
+            assert!(verify_password("segreta", &p_h, ""));
+            assert!(!verify_password("sbagliata", &p_h, ""));
+        }
+    }
+
+    #[test]
+    fn plain_identity_fields_get_sealed() {
+        let key = generate_vault_key();
+        let vault_id = generate_id();
+        let mut data = AppData::default();
+        data.ps.push(PasswordEntry {
+            id: generate_id(),
+            kind: EntryKind::Identity,
+            data: vec![CustomField {
+                id: "full_name".to_string(),
+                name: "Nome completo".to_string(),
+                value: "Mario Rossi".to_string(),
+                nonce: String::new(),
+                secret: false,
+            }],
+            ..Default::default()
+        });
+
+        assert!(data.seal_typed_fields(&key, &vault_id).unwrap());
+        let entry = &data.ps[0];
+        let field = &entry.data[0];
+        assert!(field.secret);
+        assert_ne!(field.value, "Mario Rossi");
+        assert_eq!(decrypt_field(entry, field, &key, &vault_id).unwrap().as_str(), "Mario Rossi");
+        assert!(!data.seal_typed_fields(&key, &vault_id).unwrap());
+    }
+
+    #[test]
+    fn password_is_bound_to_entry_and_vault() {
+        let key = generate_vault_key();
+        let vault_id = generate_id();
+        let mut first = sealed_entry("prima", &key, &vault_id);
+        let mut second = sealed_entry("seconda", &key, &vault_id);
+        assert_eq!(decrypt_password(&first, &key, &vault_id).unwrap().as_str(), "prima");
+        assert_eq!(decrypt_password(&second, &key, &vault_id).unwrap().as_str(), "seconda");
+
+        // Un altro vault con la stessa chiave non può leggere le password
+        assert!(decrypt_password(&first, &key, &generate_id()).is_err());
+
+        // e_c e nonce scambiati tra due entry dello stesso vault
+        std::mem::swap(&mut first.e_c, &mut second.e_c);
+        std::mem::swap(&mut first.nonce, &mut second.nonce);
+        assert!(decrypt_password(&first, &key, &vault_id).is_err());
+        assert!(decrypt_password(&second, &key, &vault_id).is_err());
+    }
+}
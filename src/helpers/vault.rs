@@ -0,0 +1,731 @@
+use crate::helpers::utils::{
+    AppData, Folder, GeneratorProfile, KdfParams, PasswordEntry, SecretKey, Settings, TrashedEntry,
+    UNLOCK_PASSWORD, UserData, WrappedKey, decrypt_bytes, encrypt_bytes, is_legacy_hash, unwrap_key,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use zeroize::Zeroizing;
+
+#[derive(Debug, Clone)]
+pub enum VaultError {
+    Io(String),
+    Permission(String),
+    Parse(String),
+    Crypto(String),
+    VersionMismatch { found: u32, supported: u32 },
+    TooLarge { size: u64, limit: u64 },
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VaultError::Io(e) => write!(f, "Errore di lettura/scrittura: {}", e),
+            VaultError::Permission(e) => write!(f, "Permesso negato: {}", e),
+            VaultError::Parse(e) => write!(f, "File non valido: {}", e),
+            VaultError::Crypto(e) => write!(f, "Errore di crittografia: {}", e),
+            VaultError::VersionMismatch { found, supported } => write!(
+                f,
+                "Il vault usa il formato {} ma questa versione supporta fino al {}",
+                found, supported
+            ),
+            VaultError::TooLarge { size, limit } => write!(
+                f,
+                "Il file è di {} KB, il limite è {} KB",
+                size / 1024,
+                limit / 1024
+            ),
+        }
+    }
+}
+
+impl VaultError {
+    pub fn io(path: &Path, e: io::Error) -> Self {
+        let message = format!("{}: {}", path.display(), e);
+        if e.kind() == ErrorKind::PermissionDenied {
+            VaultError::Permission(message)
+        } else {
+            VaultError::Io(message)
+        }
+    }
+}
+
+// Parte in chiaro del file: solo ciò che serve per sbloccare il vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultHeader {
+    pub format_version: u32,
+    pub kdf: KdfParams,
+    pub p_h: String, // Password hash (stringa PHC Argon2id)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub salt: String, // Solo per i vecchi hash SHA-256
+    pub key_salt: String,
+    // Vuoto nei vault creati prima della chiave casuale: il contenuto è cifrato con quella derivata
+    #[serde(default)]
+    pub wrapped_keys: Vec<WrappedKey>,
+    // Fa parte dei dati associati di ogni password
+    #[serde(default)]
+    pub vault_id: String,
+}
+
+// Tutto il resto viene serializzato e cifrato con AES-GCM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultPayload {
+    u: String, // Username
+    ps: Vec<PasswordEntry>,
+    dark_mode: Option<bool>,
+    #[serde(default)]
+    settings: Settings,
+    #[serde(default)]
+    trash: Vec<TrashedEntry>,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    profiles: Vec<GeneratorProfile>,
+}
+
+// Contenuto del file dopo l'intestazione
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VaultContent {
+    Sealed { nonce: String, payload: String },
+    // Prodotto solo dalla migrazione del vecchio formato in chiaro, viene cifrato al primo salvataggio
+    Plain { plain: VaultPayload },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultFile {
+    pub header: VaultHeader,
+    #[serde(flatten)]
+    pub content: VaultContent,
+}
+
+impl VaultFile {
+    pub fn is_sealed(&self) -> bool {
+        matches!(self.content, VaultContent::Sealed { .. })
+    }
+}
+
+type Migration = fn(Value) -> Result<Value, VaultError>;
+
+// MIGRATIONS[n] porta un file dalla versione n alla n + 1
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+pub const FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// v0: { user: { u, p_h, salt, key_salt }, ps, dark_mode } tutto in chiaro
+fn migrate_v0_to_v1(mut value: Value) -> Result<Value, VaultError> {
+    let mut user = value
+        .get_mut("user")
+        .map(Value::take)
+        .filter(|user| !user.is_null())
+        .ok_or_else(|| VaultError::Parse("il file non contiene un utente".to_string()))?;
+    let take = |user: &mut Value, field: &str| user.get_mut(field).map(Value::take).unwrap_or_default();
+
+    Ok(json!({
+        "header": {
+            "format_version": 1,
+            "kdf": KdfParams::default(),
+            "p_h": take(&mut user, "p_h"),
+            "salt": take(&mut user, "salt"),
+            "key_salt": take(&mut user, "key_salt"),
+        },
+        "plain": {
+            "u": take(&mut user, "u"),
+            "ps": value.get_mut("ps").map(Value::take).unwrap_or_else(|| json!([])),
+            "dark_mode": value.get_mut("dark_mode").map(Value::take).unwrap_or_default(),
+        },
+    }))
+}
+
+// v2: il contenuto è cifrato con una chiave casuale, salvata cifrata in wrapped_keys.
+// I file v1 non ne hanno ancora una, viene generata al primo accesso
+fn migrate_v1_to_v2(mut value: Value) -> Result<Value, VaultError> {
+    let header = value
+        .get_mut("header")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| VaultError::Parse("intestazione mancante".to_string()))?;
+    header.insert("format_version".to_string(), json!(2));
+    header.entry("wrapped_keys").or_insert_with(|| json!([]));
+    Ok(value)
+}
+
+// v3: ogni password è cifrata con ID dell'entry e del vault come dati associati.
+// ID mancanti vengono assegnati al primo accesso, quando le password si possono ricifrare
+fn migrate_v2_to_v3(mut value: Value) -> Result<Value, VaultError> {
+    let header = value
+        .get_mut("header")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| VaultError::Parse("intestazione mancante".to_string()))?;
+    header.insert("format_version".to_string(), json!(3));
+    header.entry("vault_id").or_insert_with(|| json!(""));
+    Ok(value)
+}
+
+fn format_version(value: &Value) -> Result<u32, VaultError> {
+    if let Some(version) = value.get("header").and_then(|header| header.get("format_version")) {
+        return version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| VaultError::Parse("versione del formato non valida".to_string()));
+    }
+
+    // Il formato originale non aveva l'intestazione
+    if value.get("ps").is_some() {
+        Ok(0)
+    } else {
+        Err(VaultError::Parse("formato del file sconosciuto".to_string()))
+    }
+}
+
+fn migrate(mut value: Value) -> Result<Value, VaultError> {
+    let version = format_version(&value)?;
+    if version > FORMAT_VERSION {
+        return Err(VaultError::VersionMismatch {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+pub fn get_data_file_path() -> PathBuf {
+    let home_dir = dirs::home_dir().expect("Unable to find home directory");
+    let app_dir = home_dir.join("p_manager");
+
+    if !app_dir.exists() && fs::create_dir_all(&app_dir).is_err() {
+        return PathBuf::from("data.json");
+    }
+
+    app_dir.join("data.json")
+}
+
+fn backup_path(data_file: &Path, n: usize) -> PathBuf {
+    let mut name = data_file.as_os_str().to_owned();
+    name.push(format!(".bak.{}", n));
+    PathBuf::from(name)
+}
+
+// Scrive su un file temporaneo, lo sincronizza e lo rinomina sopra quello originale,
+// così un crash a metà scrittura non lascia mai data.json troncato
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+
+    // Rende persistente anche la rinomina
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+// Solo i file cifrati e con l'hash Argon2 possono finire nei backup: una copia del vecchio
+// formato in chiaro annullerebbe la cifratura finché il backup esiste
+fn is_sealed_file(path: &Path) -> bool {
+    matches!(read_vault(path), Ok(Some(file)) if file.is_sealed() && !is_legacy_hash(&file.header.p_h))
+}
+
+// Indice di un backup dal nome del file. staged indica i .rotating lasciati da una rinumerazione
+// interrotta delle versioni precedenti, che rinominavano tutti i backup in due passaggi
+struct BackupName {
+    staged: bool,
+    index: usize,
+}
+
+fn backup_name(data_file: &Path, path: &Path) -> Option<BackupName> {
+    let prefix = format!("{}.bak.", data_file.file_name()?.to_str()?);
+    let suffix = path.file_name()?.to_str()?.strip_prefix(&prefix)?;
+    let (index, staged) = match suffix.strip_suffix(".rotating") {
+        Some(index) => (index, true),
+        None => (suffix, false),
+    };
+    let index = index.parse().ok().filter(|index| *index > 0)?;
+    Some(BackupName { staged, index })
+}
+
+// Backup accanto a data.json, dal più recente. Non ci si ferma al primo indice mancante: dopo un
+// crash a metà rotazione possono esserci dei buchi. I .rotating rimasti sono più vecchi di tutti
+// gli altri (il primo passaggio li creava tutti) e vanno in fondo, nel loro ordine
+fn existing_backups(data_file: &Path) -> Vec<PathBuf> {
+    let dir = match data_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<(bool, usize, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = data_file.with_file_name(entry.file_name());
+            let name = backup_name(data_file, &path)?;
+            Some((name.staged, name.index, path))
+        })
+        .collect();
+    backups.sort_unstable();
+    backups.into_iter().map(|(_, _, path)| path).collect()
+}
+
+// Rinumera i backup rimasti da first in poi. Ogni rinomina sposta un file su un indice libero senza
+// cambiarne l'ordine rispetto agli altri: un crash a metà lascia al massimo dei buchi, che
+// existing_backups salta e la rotazione successiva chiude
+fn renumber_backups(data_file: &Path, kept: Vec<PathBuf>, first: usize) -> io::Result<()> {
+    let moves: Vec<(Option<BackupName>, usize, PathBuf)> = kept
+        .into_iter()
+        .enumerate()
+        .map(|(position, path)| (backup_name(data_file, &path), first + position, path))
+        .collect();
+
+    // Prima quelli che scendono, dal più recente; poi quelli che salgono, dal più vecchio.
+    // Così la destinazione è sempre libera. Infine i .rotating, che vanno dopo tutti gli altri
+    for (name, target, path) in &moves {
+        if let Some(name) = name
+            && !name.staged
+            && *target < name.index
+        {
+            fs::rename(path, backup_path(data_file, *target))?;
+        }
+    }
+    for (name, target, path) in moves.iter().rev() {
+        if let Some(name) = name
+            && !name.staged
+            && *target > name.index
+        {
+            fs::rename(path, backup_path(data_file, *target))?;
+        }
+    }
+    for (name, target, path) in &moves {
+        if name.as_ref().is_some_and(|name| name.staged) {
+            fs::rename(path, backup_path(data_file, *target))?;
+        }
+    }
+    Ok(())
+}
+
+// data.json -> data.json.bak.1 -> data.json.bak.2 -> ... fino a backup_count
+fn rotate_backups(data_file: &Path, backup_count: usize) -> io::Result<()> {
+    // Le copie non cifrate (fatte da versioni precedenti) vengono eliminate
+    let mut kept = Vec::new();
+    for path in existing_backups(data_file) {
+        if is_sealed_file(&path) {
+            kept.push(path);
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+
+    // Il primo salvataggio dopo la migrazione sovrascrive un file in chiaro: niente copia
+    let copy_current = backup_count > 0 && data_file.exists() && is_sealed_file(data_file);
+    let slots = backup_count.saturating_sub(usize::from(copy_current));
+    for path in kept.drain(slots.min(kept.len())..) {
+        fs::remove_file(path)?;
+    }
+    renumber_backups(data_file, kept, 1 + usize::from(copy_current))?;
+
+    // Copia e non rinomina: data.json deve esistere sempre. Scrittura atomica, altrimenti un crash
+    // a metà lascerebbe un bak.1 troncato
+    if copy_current {
+        write_atomic(&backup_path(data_file, 1), &fs::read(data_file)?)?;
+    }
+    Ok(())
+}
+
+// Dopo un cambio di master password o di parametri KDF i backup devono aprirsi solo con le nuove
+// credenziali. La chiave del vault non cambia: basta sostituire l'intestazione. I backup che non
+// si possono aggiornare (altro vault, chiave diversa, file illeggibili) vengono eliminati
+pub fn rewrap_backups(user: &UserData, vault_key: &[u8; 32]) -> Result<(), VaultError> {
+    let data_file = get_data_file_path();
+    let mut kept = Vec::new();
+
+    for path in existing_backups(&data_file) {
+        match read_vault(&path) {
+            Ok(Some(mut file))
+                if file.is_sealed()
+                    && file.header.vault_id == user.vault_id
+                    && unlock_vault(&file, vault_key).is_ok() =>
+            {
+                file.header.kdf = user.kdf.clone();
+                file.header.p_h = user.p_h.clone();
+                file.header.salt = user.salt.clone();
+                file.header.key_salt = user.key_salt.clone();
+                file.header.wrapped_keys = user.wrapped_keys.clone();
+
+                let json = serde_json::to_string_pretty(&file).map_err(|e| VaultError::Parse(e.to_string()))?;
+                write_atomic(&path, json.as_bytes()).map_err(|e| VaultError::io(&path, e))?;
+                kept.push(path);
+            }
+            _ => fs::remove_file(&path).map_err(|e| VaultError::io(&path, e))?,
+        }
+    }
+
+    renumber_backups(&data_file, kept, 1).map_err(|e| VaultError::io(&data_file, e))
+}
+
+// Contenuto di tutti i backup, per sapere quali allegati usano ancora
+pub fn open_backups(key: &[u8; 32]) -> Result<Vec<AppData>, VaultError> {
+    existing_backups(&get_data_file_path())
+        .iter()
+        .filter_map(|path| read_vault(path).transpose())
+        .map(|file| unlock_vault(&file?, key))
+        .collect()
+}
+
+// Backup esistenti, dal più recente, con la data di modifica
+pub fn list_backups() -> Vec<(PathBuf, Option<SystemTime>)> {
+    existing_backups(&get_data_file_path())
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+// Sostituisce data.json con un backup, dopo averlo verificato.
+// Il file illeggibile viene conservato come data.json.corrupt
+pub fn restore_backup(backup: &Path) -> Result<(), VaultError> {
+    read_vault(backup)?.ok_or_else(|| VaultError::Parse("il backup è vuoto".to_string()))?;
+
+    let data_file = get_data_file_path();
+    let contents = fs::read(backup).map_err(|e| VaultError::io(backup, e))?;
+
+    // Un vault leggibile ma in chiaro non va conservato: verrebbe solo lasciato scoperto
+    let readable_plain = matches!(read_vault(&data_file), Ok(Some(file)) if !file.is_sealed());
+    if data_file.exists() && !readable_plain {
+        let mut corrupt = data_file.as_os_str().to_owned();
+        corrupt.push(".corrupt");
+        let corrupt = PathBuf::from(corrupt);
+        fs::copy(&data_file, &corrupt).map_err(|e| VaultError::io(&corrupt, e))?;
+    }
+
+    write_atomic(&data_file, &contents).map_err(|e| VaultError::io(&data_file, e))
+}
+
+// Ok(None) se non esiste ancora un vault, Err se il file esiste ma non è leggibile:
+// in quel caso non bisogna mai sovrascriverlo
+pub fn load_vault() -> Result<Option<VaultFile>, VaultError> {
+    read_vault(&get_data_file_path())
+}
+
+fn read_vault(data_file: &Path) -> Result<Option<VaultFile>, VaultError> {
+    let data = match fs::read_to_string(data_file) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(VaultError::io(data_file, e)),
+    };
+
+    let value: Value = serde_json::from_str(&data)
+        .map_err(|e| VaultError::Parse(format!("{}: {}", data_file.display(), e)))?;
+
+    // Il vecchio formato scriveva il file anche senza utente registrato
+    if value.get("header").is_none()
+        && value.get("user").is_some_and(Value::is_null)
+        && value.get("ps").and_then(Value::as_array).is_some_and(Vec::is_empty)
+    {
+        return Ok(None);
+    }
+
+    let version = format_version(&value)?;
+    let file: VaultFile = serde_json::from_value(migrate(value)?)
+        .map_err(|e| VaultError::Parse(format!("{}: {}", data_file.display(), e)))?;
+
+    // Solo il formato originale era in chiaro: in un file più recente un contenuto non cifrato
+    // non è autenticato da niente e verrebbe accettato e ricifrato senza controlli
+    if !file.is_sealed() && version > 0 {
+        return Err(VaultError::Parse(format!(
+            "{}: contenuto non cifrato in un file della versione {}",
+            data_file.display(),
+            version
+        )));
+    }
+    Ok(Some(file))
+}
+
+// Chiave che cifra il contenuto: quella casuale se presente, altrimenti (vault v1) quella derivata
+pub fn open_vault_key(header: &VaultHeader, derived_key: &SecretKey) -> Result<SecretKey, VaultError> {
+    match header.wrapped_keys.iter().find(|wrapped| wrapped.method == UNLOCK_PASSWORD) {
+        Some(wrapped) => unwrap_key(wrapped, derived_key).map_err(VaultError::Crypto),
+        None => Ok(derived_key.clone()),
+    }
+}
+
+pub fn unlock_vault(file: &VaultFile, key: &[u8; 32]) -> Result<AppData, VaultError> {
+    let payload = match &file.content {
+        VaultContent::Sealed { nonce, payload } => {
+            let plaintext = decrypt_bytes(payload, nonce, b"", key).map_err(VaultError::Crypto)?;
+            serde_json::from_slice(&plaintext).map_err(|e| VaultError::Parse(e.to_string()))?
+        }
+        VaultContent::Plain { plain } => plain.clone(),
+    };
+
+    Ok(AppData {
+        user: Some(UserData {
+            u: payload.u,
+            kdf: file.header.kdf.clone(),
+            p_h: file.header.p_h.clone(),
+            salt: file.header.salt.clone(),
+            key_salt: file.header.key_salt.clone(),
+            wrapped_keys: file.header.wrapped_keys.clone(),
+            vault_id: file.header.vault_id.clone(),
+        }),
+        ps: payload.ps,
+        dark_mode: payload.dark_mode,
+        settings: payload.settings,
+        trash: payload.trash,
+        folders: payload.folders,
+        profiles: payload.profiles,
+    })
+}
+
+pub fn save_data(data: &AppData, key: &[u8; 32]) -> Result<(), VaultError> {
+    write_vault(data, key, true)
+}
+
+// Per modifiche minori e frequenti (es. ultimo utilizzo) che non devono far ruotare i backup
+pub fn save_data_without_backup(data: &AppData, key: &[u8; 32]) -> Result<(), VaultError> {
+    write_vault(data, key, false)
+}
+
+fn write_vault(data: &AppData, key: &[u8; 32], backup: bool) -> Result<(), VaultError> {
+    let user = data
+        .user
+        .as_ref()
+        .ok_or_else(|| VaultError::Parse("nessun utente registrato".to_string()))?;
+
+    let payload = VaultPayload {
+        u: user.u.clone(),
+        ps: data.ps.clone(),
+        dark_mode: data.dark_mode,
+        settings: data.settings.clone(),
+        trash: data.trash.clone(),
+        folders: data.folders.clone(),
+        profiles: data.profiles.clone(),
+    };
+
+    let plaintext = serde_json::to_vec(&payload)
+        .map(Zeroizing::new)
+        .map_err(|e| VaultError::Parse(e.to_string()))?;
+    let (payload, nonce) = encrypt_bytes(&plaintext, b"", key).map_err(VaultError::Crypto)?;
+
+    let file = VaultFile {
+        header: VaultHeader {
+            format_version: FORMAT_VERSION,
+            kdf: user.kdf.clone(),
+            p_h: user.p_h.clone(),
+            salt: user.salt.clone(),
+            key_salt: user.key_salt.clone(),
+            wrapped_keys: user.wrapped_keys.clone(),
+            vault_id: user.vault_id.clone(),
+        },
+        content: VaultContent::Sealed { nonce, payload },
+    };
+
+    let json = serde_json::to_string_pretty(&file).map_err(|e| VaultError::Parse(e.to_string()))?;
+    let data_file = get_data_file_path();
+    let rotated = if backup {
+        rotate_backups(&data_file, data.settings.backup_count)
+    } else {
+        Ok(())
+    };
+    rotated
+        .and_then(|_| write_atomic(&data_file, json.as_bytes()))
+        .map_err(|e| VaultError::io(&data_file, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cartella temporanea con un data.json finto, eliminata alla fine del test
+    struct TempVault {
+        dir: PathBuf,
+        data_file: PathBuf,
+    }
+
+    impl TempVault {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("p_manager_test_{}", crate::helpers::utils::generate_id()));
+            fs::create_dir_all(&dir).unwrap();
+            let data_file = dir.join("data.json");
+            Self { dir, data_file }
+        }
+
+        fn write_sealed(&self, path: &Path, marker: &str) {
+            let file = json!({
+                "header": {
+                    "format_version": FORMAT_VERSION,
+                    "kdf": KdfParams::default(),
+                    "p_h": "$argon2id$v=19$m=19456,t=2,p=1$c2FsZQ$aGFzaA",
+                    "key_salt": "",
+                    "vault_id": "vault",
+                },
+                "nonce": "",
+                "payload": marker,
+            });
+            fs::write(path, file.to_string()).unwrap();
+        }
+
+        fn write_plain(&self, path: &Path) {
+            let file = json!({
+                "user": { "u": "mario", "p_h": "abc", "salt": "sale", "key_salt": "chiave" },
+                "ps": [],
+                "dark_mode": null,
+            });
+            fs::write(path, file.to_string()).unwrap();
+        }
+
+        fn save(&self, marker: &str, backup_count: usize) {
+            rotate_backups(&self.data_file, backup_count).unwrap();
+            self.write_sealed(&self.data_file, marker);
+        }
+
+        fn backup(&self, n: usize) -> PathBuf {
+            backup_path(&self.data_file, n)
+        }
+
+        // Contenuto dei backup, dal più recente
+        fn markers(&self) -> Vec<String> {
+            existing_backups(&self.data_file)
+                .iter()
+                .map(|path| match read_vault(path).unwrap().unwrap().content {
+                    VaultContent::Sealed { payload, .. } => payload,
+                    VaultContent::Plain { .. } => "in chiaro".to_string(),
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for TempVault {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn rotation_keeps_the_newest_copies() {
+        let vault = TempVault::new();
+        for marker in ["s1", "s2", "s3", "s4", "s5"] {
+            vault.save(marker, 3);
+        }
+        assert_eq!(vault.markers(), ["s4", "s3", "s2"]);
+        assert!(!vault.backup(4).exists());
+
+        // Meno copie: si eliminano le più vecchie
+        vault.save("s6", 2);
+        assert_eq!(vault.markers(), ["s5", "s4"]);
+        assert!(!vault.backup(3).exists());
+
+        // Più copie: quelle esistenti restano
+        vault.save("s7", 4);
+        vault.save("s8", 4);
+        assert_eq!(vault.markers(), ["s7", "s6", "s5", "s4"]);
+
+        vault.save("s9", 0);
+        assert!(vault.markers().is_empty());
+    }
+
+    #[test]
+    fn unsealed_files_never_become_backups() {
+        let vault = TempVault::new();
+        vault.write_sealed(&vault.backup(1), "s1");
+        vault.write_plain(&vault.backup(2));
+        vault.write_plain(&vault.data_file);
+
+        rotate_backups(&vault.data_file, 3).unwrap();
+        assert_eq!(vault.markers(), ["s1"]);
+        assert!(!vault.backup(2).exists());
+    }
+
+    #[test]
+    fn interrupted_rotation_is_recovered() {
+        let vault = TempVault::new();
+        vault.write_sealed(&vault.backup(2), "s3");
+        vault.write_sealed(&vault.backup(5), "s2");
+        let mut staged = vault.backup(1).into_os_string();
+        staged.push(".rotating");
+        vault.write_sealed(Path::new(&staged), "s1");
+
+        // Nessun backup nascosto dai buchi, i .rotating in fondo
+        assert_eq!(vault.markers(), ["s3", "s2", "s1"]);
+
+        vault.write_sealed(&vault.data_file, "s4");
+        rotate_backups(&vault.data_file, 5).unwrap();
+        assert_eq!(vault.markers(), ["s4", "s3", "s2", "s1"]);
+        assert!((1..=4).all(|n| vault.backup(n).exists()));
+        assert!(!Path::new(&staged).exists());
+    }
+
+    #[test]
+    fn v0_file_migrates_to_current_format() {
+        let v0 = json!({
+            "user": { "u": "mario", "p_h": "abc", "salt": "sale", "key_salt": "chiave" },
+            "ps": [{ "name": "Gmail", "u": "mario@example.com", "e_c": "ZWM=", "nonce": "bm9uY2U=" }],
+            "dark_mode": true,
+        });
+
+        let file: VaultFile = serde_json::from_value(migrate(v0).unwrap()).unwrap();
+        assert_eq!(file.header.format_version, FORMAT_VERSION);
+        assert_eq!(file.header.p_h, "abc");
+        assert_eq!(file.header.salt, "sale");
+        assert_eq!(file.header.key_salt, "chiave");
+        assert!(file.header.wrapped_keys.is_empty());
+        assert!(file.header.vault_id.is_empty());
+
+        let VaultContent::Plain { plain } = file.content else {
+            panic!("il vecchio formato deve restare in chiaro fino al primo salvataggio");
+        };
+        assert_eq!(plain.u, "mario");
+        assert_eq!(plain.dark_mode, Some(true));
+        assert_eq!(plain.ps.len(), 1);
+        assert_eq!(plain.ps[0].name, "Gmail");
+        assert!(plain.ps[0].id.is_empty());
+    }
+
+    #[test]
+    fn plain_content_only_from_v0() {
+        let vault = TempVault::new();
+        vault.write_plain(&vault.data_file);
+        assert!(!read_vault(&vault.data_file).unwrap().unwrap().is_sealed());
+
+        let current = json!({
+            "header": {
+                "format_version": FORMAT_VERSION,
+                "kdf": KdfParams::default(),
+                "p_h": "$argon2id$v=19$m=19456,t=2,p=1$c2FsZQ$aGFzaA",
+                "key_salt": "",
+                "vault_id": "vault",
+            },
+            "plain": { "u": "mario", "ps": [], "dark_mode": null },
+        });
+        fs::write(&vault.data_file, current.to_string()).unwrap();
+        assert!(matches!(read_vault(&vault.data_file), Err(VaultError::Parse(_))));
+    }
+
+    #[test]
+    fn newer_format_is_refused() {
+        let newer = json!({ "header": { "format_version": FORMAT_VERSION + 1 } });
+        match migrate(newer) {
+            Err(VaultError::VersionMismatch { found, supported }) => {
+                assert_eq!((found, supported), (FORMAT_VERSION + 1, FORMAT_VERSION));
+            }
+            other => panic!("atteso VersionMismatch, ottenuto {:?}", other),
+        }
+    }
+}
@@ -3,7 +3,11 @@
 mod helpers;
 mod pages;
 
-use crate::helpers::utils::{AppData, PasswordEntry, UserData, load_data};
+use crate::helpers::utils::{
+    AppData, EntryExtras, KdfParams, PasswordEntry, SecretKey, SecretString, Settings, UserData, secret_string,
+};
+use crate::helpers::generate_password::GeneratorOptions;
+use crate::helpers::vault::{VaultError, VaultFile, load_vault};
 use eframe::egui;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -15,33 +19,87 @@ pub enum AppState {
     Main,
 }
 
+// Ordinamento della lista password
+#[derive(Clone, Copy, PartialEq)]
+pub enum ListSort {
+    Insertion,
+    Name,
+    RecentlyUsed,
+    RecentlyModified,
+    RecentlyCreated,
+}
+
+impl ListSort {
+    pub const ALL: [ListSort; 5] = [
+        ListSort::Insertion,
+        ListSort::Name,
+        ListSort::RecentlyUsed,
+        ListSort::RecentlyModified,
+        ListSort::RecentlyCreated,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ListSort::Insertion => "↕ Inserimento",
+            ListSort::Name => "🔤 Nome",
+            ListSort::RecentlyUsed => "🕘 Usate di recente",
+            ListSort::RecentlyModified => "✏ Modificate di recente",
+            ListSort::RecentlyCreated => "➕ Create di recente",
+        }
+    }
+
+    // Le entry senza data finiscono in fondo
+    pub fn sort(self, entries: &mut [PasswordEntry]) {
+        match self {
+            ListSort::Insertion => {}
+            ListSort::Name => entries.sort_by_key(|entry| entry.name.to_lowercase()),
+            ListSort::RecentlyUsed => entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used_at)),
+            ListSort::RecentlyModified => entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified_at)),
+            ListSort::RecentlyCreated => entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at)),
+        }
+    }
+}
+
 pub struct PasswordManagerApp {
     pub state: AppState,
 
     // Campi per registrazione/login
     pub reg_username: String,
-    pub reg_password: String,
-    pub reg_confirm_password: String,
+    pub reg_password: SecretString,
+    pub reg_confirm_password: SecretString,
 
     pub login_username: String,
-    pub login_password: String,
+    pub login_password: SecretString,
+
+    // Vault cifrato letto dal disco, da sbloccare al login
+    pub locked_vault: Option<VaultFile>,
+    // Errore di lettura del vault: il file non va mai sovrascritto
+    pub load_error: Option<VaultError>,
 
     // Dati dell'app
     pub app_data: AppData,
     pub current_user: Option<UserData>,
 
-    pub encryption_key: Option<[u8; 32]>,
+    pub encryption_key: Option<SecretKey>,
 
     // Campi per aggiungere password
     pub new_entry_name: String,
     pub new_entry_username: String,
-    pub new_entry_password: String,
+    pub new_entry_password: SecretString,
+    pub new_entry_extras: EntryExtras,
 
     // Campi per modificare password
-    pub edit_service_name: String,
-    pub edit_new_username: String,
-    pub edit_new_password: String,
-    pub edit_confirm_password: String,
+    pub edit_entry_id: Option<String>,
+    pub edit_entry_name: String,
+    pub edit_entry_username: String,
+    pub edit_new_password: SecretString,
+    pub edit_confirm_password: SecretString,
+    pub edit_entry_extras: EntryExtras,
+
+    // Campi per cambiare la master password
+    pub change_old_password: SecretString,
+    pub change_new_password: SecretString,
+    pub change_confirm_password: SecretString,
 
     // Messaggi di errore/successo
     pub message: String,
@@ -50,13 +108,24 @@ pub struct PasswordManagerApp {
     // Tema
     pub dark_mode: bool,
 
+    // Impostazioni in modifica, applicate solo al salvataggio
+    pub settings_draft: Settings,
+    pub kdf_draft: KdfParams,
+    pub kdf_password: SecretString,
+    pub kdf_benchmark: Option<Result<Duration, String>>,
+
     // Ricerca
     pub search_query: String,
+    pub list_sort: ListSort,
+    pub selected_folder: Option<String>, // None = tutte le password
+    pub folder_name_input: String,
 
-    // Mostra password temporaneamente (indice -> (password, tempo_inizio))
-    pub shown_passwords: HashMap<usize, (String, Instant)>,
+    // Mostra password temporaneamente (ID entry -> (password, tempo_inizio))
+    pub shown_passwords: HashMap<String, (SecretString, Instant)>,
+    pub show_trash: bool,
+    pub details_entry_id: Option<String>, // Entry con il dettaglio aperto nella lista
 
-    // Tab attivo (0 = Aggiungi, 1 = Modifica)
+    // Tab attivo (0 = Aggiungi, 1 = Modifica, 2 = Impostazioni)
     pub active_tab: usize,
 
     // Booleans per i checkbox mostra password
@@ -66,46 +135,77 @@ pub struct PasswordManagerApp {
     // Booleans per i popup
     pub show_popup_add: bool,
     pub show_popup_edit: bool,
+    pub generator_options: GeneratorOptions, // Condivise dai popup di aggiunta e modifica
+
+    // Profili del generatore nelle impostazioni
+    pub profile_name_input: String,
+    pub profile_draft: GeneratorOptions,
+    pub editing_profile_id: Option<String>, // None = nuovo profilo
 }
 
 impl Default for PasswordManagerApp {
     fn default() -> Self {
-        let app_data = load_data();
-        let state = if app_data.user.is_some() {
+        let (locked_vault, load_error) = match load_vault() {
+            Ok(locked_vault) => (locked_vault, None),
+            Err(e) => (None, Some(e)),
+        };
+        let state = if locked_vault.is_some() || load_error.is_some() {
             AppState::Login
         } else {
             AppState::Registration
         };
 
-        let dark_mode = app_data.dark_mode.unwrap_or(true);
+        // Il tema è salvato nel vault cifrato: prima del login si usa quello scuro
+        let dark_mode = true;
 
         Self {
             state,
             reg_username: String::new(),
-            reg_password: String::new(),
-            reg_confirm_password: String::new(),
+            reg_password: secret_string(),
+            reg_confirm_password: secret_string(),
             login_username: String::new(),
-            login_password: String::new(),
-            app_data,
+            login_password: secret_string(),
+            locked_vault,
+            load_error,
+            app_data: AppData::default(),
             current_user: None,
             encryption_key: None,
             new_entry_name: String::new(),
             new_entry_username: String::new(),
-            new_entry_password: String::new(),
-            edit_service_name: String::new(),
-            edit_new_username: String::new(),
-            edit_new_password: String::new(),
-            edit_confirm_password: String::new(),
+            new_entry_password: secret_string(),
+            new_entry_extras: EntryExtras::default(),
+            edit_entry_id: None,
+            edit_entry_name: String::new(),
+            edit_entry_username: String::new(),
+            edit_new_password: secret_string(),
+            edit_confirm_password: secret_string(),
+            edit_entry_extras: EntryExtras::default(),
+            change_old_password: secret_string(),
+            change_new_password: secret_string(),
+            change_confirm_password: secret_string(),
             message: String::new(),
             message_color: egui::Color32::GREEN,
             dark_mode,
+            settings_draft: Settings::default(),
+            kdf_draft: KdfParams::default(),
+            kdf_password: secret_string(),
+            kdf_benchmark: None,
             search_query: String::new(),
+            list_sort: ListSort::Insertion,
+            selected_folder: None,
+            folder_name_input: String::new(),
             shown_passwords: HashMap::new(),
+            show_trash: false,
+            details_entry_id: None,
             active_tab: 0,
             show_password: false,
             show_password1: false,
             show_popup_add: false,
             show_popup_edit: false,
+            generator_options: GeneratorOptions::default(),
+            profile_name_input: String::new(),
+            profile_draft: GeneratorOptions::default(),
+            editing_profile_id: None,
         }
     }
 }
@@ -115,13 +215,13 @@ impl eframe::App for PasswordManagerApp {
         
         // Pulizia delle password mostrate dopo 10 secondi
         let now = Instant::now();
-        let expired_keys: Vec<usize> = self
+        let expired_keys: Vec<String> = self
             .shown_passwords
             .iter()
             .filter(|(_, (_, start_time))| {
                 now.duration_since(*start_time) > Duration::from_secs(10)
             })
-            .map(|(&key, _)| key)
+            .map(|(key, _)| key.clone())
             .collect();
 
         for key in expired_keys {
@@ -131,7 +231,13 @@ impl eframe::App for PasswordManagerApp {
         // Per migliorare la performance e possibili bug:
         // 1: Aggiorno la GUI solo se sono nella pagina Main
         // 2: Aggiorno la GUI solo se non ci sono password "scoperte" e quindi non devo tenere il timer aggiornato
-        if !self.shown_passwords.is_empty() && self.state == AppState::Main {
+        // 3: ...oppure se ci sono codici a tempo, che cambiano da soli
+        let has_otp = self
+            .app_data
+            .ps
+            .iter()
+            .any(|entry| entry.otp.as_ref().is_some_and(|otp| otp.kind.is_time_based()));
+        if (!self.shown_passwords.is_empty() || has_otp) && self.state == AppState::Main {
             ctx.request_repaint_after(Duration::from_secs(1));
         }
 
@@ -231,7 +337,7 @@ impl eframe::App for PasswordManagerApp {
                         self.toggle_theme();
                     }
 
-                    if self.state == AppState::Login || self.state == AppState::Login {
+                    if self.state == AppState::Login || self.state == AppState::Registration {
                         ui.separator();
                         if ui.button("🚪 Exit").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
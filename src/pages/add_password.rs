@@ -1,5 +1,8 @@
 use crate::PasswordManagerApp;
 use crate::helpers::generate_password::generate_password;
+use super::entry_extras::{show_extras_editor, show_kind_selector, show_typed_fields_editor};
+use super::folders::folder_combo;
+use super::generator::{profile_combo, show_generator_options};
 use eframe::egui;
 
 impl PasswordManagerApp {
@@ -15,84 +18,149 @@ impl PasswordManagerApp {
                         ui.add_space(15.0);
 
                         ui.vertical(|ui| {
+                            ui.label("🗂 Tipo");
+                            show_kind_selector(ui, &mut self.new_entry_extras, "add_entry_kind");
+                            ui.add_space(10.0);
+
                             ui.label("🏷 Nome servizio");
                             ui.add(
                                 egui::TextEdit::singleline(&mut self.new_entry_name)
                                     .hint_text("es. Gmail, Facebook...")
                                     .min_size(egui::vec2(230.0, 25.0)),
                             );
-                            ui.add_space(10.0);
 
-                            ui.label("👤 Username");
-                            ui.add(
-                                egui::TextEdit::singleline(&mut self.new_entry_username)
-                                    .hint_text("username o email")
-                                    .min_size(egui::vec2(230.0, 25.0)),
-                            );
+                            // Avvisa se esiste già una voce con lo stesso nome
+                            let duplicates: Vec<&str> = self
+                                .app_data
+                                .entries_named(&self.new_entry_name)
+                                .map(|entry| entry.u.as_str())
+                                .collect();
+                            if !duplicates.is_empty() {
+                                if duplicates.contains(&self.new_entry_username.as_str()) {
+                                    ui.colored_label(egui::Color32::YELLOW, "⚠ Esiste già una voce con questo nome e username");
+                                } else {
+                                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ Esistono già {} voci con questo nome", duplicates.len()));
+                                }
+                            }
                             ui.add_space(10.0);
 
-                            ui.label("🔑 Password");
+                            if self.new_entry_extras.kind.has_password() {
+                                ui.label("👤 Username");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.new_entry_username)
+                                        .hint_text("username o email")
+                                        .min_size(egui::vec2(230.0, 25.0)),
+                                );
+                                ui.add_space(10.0);
+
+                                ui.label("🔑 Password");
                             
-                            let password_response = ui.add(
-                                egui::TextEdit::singleline(&mut self.new_entry_password)
-                                    .password(!self.show_password)
-                                    .hint_text("password sicura")
-                                    .min_size(egui::vec2(230.0, 25.0)),
-                            );
+                                let (password_response, dice_response) = ui
+                                    .horizontal(|ui| {
+                                        let password_response = ui.add(
+                                            egui::TextEdit::singleline(&mut *self.new_entry_password)
+                                                .password(!self.show_password)
+                                                .hint_text("password sicura")
+                                                .min_size(egui::vec2(230.0, 25.0)),
+                                        );
+                                        let dice_response = ui.button("🎲").on_hover_text("Genera una password");
+                                        (password_response, dice_response)
+                                    })
+                                    .inner;
                             
-                            // Quando il field password è vuoto e prende il focus mostra il popup
-                            if password_response.gained_focus() && self.new_entry_password.is_empty() {
-                                self.show_popup_add = true;
-                            }
+                                // Quando il field password è vuoto e prende il focus mostra il popup
+                                if password_response.gained_focus() && self.new_entry_password.is_empty() {
+                                    self.show_popup_add = true;
+                                }
+                                if dice_response.clicked() {
+                                    self.show_popup_add = !self.show_popup_add;
+                                }
                             
-                            // Suggerisci password pupup
-                            if self.show_popup_add {
-                                let popup_id = ui.make_persistent_id("password_gen_popup_add");
-                                egui::Area::new(popup_id)
-                                    .fixed_pos(password_response.rect.left_bottom() + egui::vec2(0.0, 5.0))
-                                    .show(ui.ctx(), |ui| {
-                                        egui::Frame::popup(ui.style())
-                                            .show(ui, |ui| {
-                                                ui.set_min_width(230.0);
-                                                ui.vertical(|ui| {
-                                                    ui.label("🎲 Vuoi generare una password sicura?");
-                                                    ui.add_space(8.0);
-                                                    
-                                                    ui.horizontal(|ui| {
-                                                        if ui.button("✅ Genera").clicked() {
-                                                            self.new_entry_password = generate_password();
-                                                            self.show_popup_add = false;
+                                // Suggerisci password pupup
+                                if self.show_popup_add {
+                                    let popup_id = ui.make_persistent_id("password_gen_popup_add");
+                                    egui::Area::new(popup_id)
+                                        .fixed_pos(password_response.rect.left_bottom() + egui::vec2(0.0, 5.0))
+                                        .show(ui.ctx(), |ui| {
+                                            egui::Frame::popup(ui.style())
+                                                .show(ui, |ui| {
+                                                    ui.set_min_width(230.0);
+                                                    ui.vertical(|ui| {
+                                                        ui.label("🎲 Vuoi generare una password sicura?");
+                                                        ui.add_space(8.0);
+                                                        // Con un profilo associato valgono le sue regole
+                                                        let profile = self
+                                                            .new_entry_extras
+                                                            .generator_profile
+                                                            .as_deref()
+                                                            .and_then(|id| self.app_data.profile(id))
+                                                            .cloned();
+                                                        match &profile {
+                                                            Some(profile) => {
+                                                                ui.small(format!("📐 {} • {}", profile.name, profile.options.summary()));
+                                                            }
+                                                            None => show_generator_options(ui, &mut self.generator_options, "add_generator_options"),
                                                         }
+                                                        ui.add_space(8.0);
+                                                    
+                                                        ui.horizontal(|ui| {
+                                                            if ui.button("✅ Genera").clicked() {
+                                                                let options = profile.as_ref().map_or(&self.generator_options, |profile| &profile.options);
+                                                                match generate_password(options) {
+                                                                    Ok(password) => {
+                                                                        // Nel buffer esistente, che ha già la capacità per le modifiche
+                                                                        self.new_entry_password.clear();
+                                                                        self.new_entry_password.push_str(&password);
+                                                                        self.show_popup_add = false;
+                                                                    }
+                                                                    Err(e) => {
+                                                                        self.message = e;
+                                                                        self.message_color = egui::Color32::RED;
+                                                                    }
+                                                                }
+                                                            }
                                                         
-                                                        if ui.button("❌ No grazie").clicked() {
-                                                            self.show_popup_add = false;
-                                                        }
+                                                            if ui.button("❌ No grazie").clicked() {
+                                                                self.show_popup_add = false;
+                                                            }
+                                                        });
                                                     });
                                                 });
-                                            });
-                                    });
-                            }
+                                        });
+                                }
                             
-                            // Chiudo il suggerimentro quando avviene un click fuori dal field password
-                            if self.show_popup_add && ui.input(|i| i.pointer.any_click()) {
-                                let popup_id = ui.make_persistent_id("password_gen_popup_add");
-                                if let Some(area_response) = ui.ctx().memory(|mem| {
-                                    mem.area_rect(popup_id)
-                                }) {
-                                    if let Some(pointer_pos) = ui.input(|i| i.pointer.interact_pos()) {
-                                        if !area_response.contains(pointer_pos) && !password_response.rect.contains(pointer_pos) {
-                                            self.show_popup_add = false;
-                                        }
+                                // Chiudo il suggerimentro quando avviene un click fuori dal field password
+                                if self.show_popup_add && ui.input(|i| i.pointer.any_click()) {
+                                    let popup_id = ui.make_persistent_id("password_gen_popup_add");
+                                    if let Some(area_response) = ui.ctx().memory(|mem| {
+                                        mem.area_rect(popup_id)
+                                    }) && let Some(pointer_pos) = ui.input(|i| i.pointer.interact_pos())
+                                        && !area_response.contains(pointer_pos) && !password_response.rect.contains(pointer_pos)
+                                        && !dice_response.rect.contains(pointer_pos) {
+                                        self.show_popup_add = false;
                                     }
                                 }
-                            }
                             
-                            ui.checkbox(&mut self.show_password, "Mostra");
+                                ui.checkbox(&mut self.show_password, "Mostra");
+                                ui.add_space(10.0);
+
+                                ui.label("📐 Profilo generatore");
+                                profile_combo(ui, &self.app_data, &mut self.new_entry_extras.generator_profile, "add_entry_profile");
+                            } else {
+                                show_typed_fields_editor(ui, &mut self.new_entry_extras);
+                            }
+                            ui.add_space(10.0);
+
+                            ui.label("📁 Cartella");
+                            folder_combo(ui, &self.app_data, &mut self.new_entry_extras.folder, "add_entry_folder");
+                            ui.add_space(10.0);
+
+                            show_extras_editor(ui, &mut self.new_entry_extras, "add_entry_extras");
                             ui.add_space(15.0);
                         });
 
                         if ui
-                            .add_sized([230.0, 35.0], egui::Button::new("💾 Salva Password"))
+                            .add_sized([230.0, 35.0], egui::Button::new("💾 Salva"))
                             .clicked()
                         {
                             self.show_password = false;
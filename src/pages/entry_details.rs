@@ -0,0 +1,196 @@
+use crate::PasswordManagerApp;
+use crate::PasswordEntry;
+use crate::helpers::utils::{
+    CustomField, confirm_dialog, decrypt_field, decrypt_previous_password, format_age,
+};
+use eframe::egui;
+use std::time::Instant;
+
+impl PasswordManagerApp {
+    pub fn show_entry_details(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, entry: &PasswordEntry) {
+        let age = |timestamp: Option<u64>| timestamp.map_or("—".to_string(), format_age);
+        ui.small(format!("➕ Creata: {}", age(entry.created_at)));
+        ui.small(format!("✏ Modificata: {}", age(entry.modified_at)));
+        ui.small(format!("🕘 Ultimo utilizzo: {}", age(entry.last_used_at)));
+        ui.add_space(5.0);
+
+        if entry.urls.len() > 1 {
+            ui.label("🌐 Siti web");
+            for url in &entry.urls {
+                ui.hyperlink(url);
+            }
+            ui.add_space(5.0);
+        }
+
+        if !entry.notes.is_empty() {
+            ui.label("📝 Note");
+            ui.label(&entry.notes);
+            ui.add_space(5.0);
+        }
+
+        if !entry.data.is_empty() {
+            ui.label(entry.kind.label());
+            for typed in entry.kind.fields() {
+                if let Some(field) = entry.data_field(typed.key) {
+                    self.show_field_row(ctx, ui, entry, field, typed.label);
+                }
+            }
+            ui.add_space(5.0);
+        }
+
+        if !entry.fields.is_empty() {
+            ui.label("🧩 Campi personalizzati");
+            for field in &entry.fields {
+                self.show_field_row(ctx, ui, entry, field, &field.name);
+            }
+            ui.add_space(5.0);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("📎 Allegati");
+            if ui.small_button("➕").on_hover_text("Allega un file").clicked() {
+                self.attach_file(&entry.id);
+            }
+        });
+        for attachment in &entry.attachments {
+            ui.horizontal(|ui| {
+                ui.label(&attachment.name);
+                ui.small(format!("{} KB • {}", attachment.size.div_ceil(1024), format_age(attachment.added_at)));
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("🗑").on_hover_text("Elimina allegato").clicked()
+                        && confirm_dialog(
+                            "Conferma Eliminazione",
+                            "Sei sicuro di voler eliminare questo allegato? Questa azione non può essere annullata.",
+                        )
+                    {
+                        self.remove_attachment(&entry.id, &attachment.id);
+                    }
+
+                    if ui.small_button("💾").on_hover_text("Salva con nome").clicked() {
+                        self.save_attachment_as(&entry.id, &attachment.id);
+                    }
+                });
+            });
+        }
+        ui.add_space(5.0);
+
+        // Le entry che non sono login hanno una cronologia solo se lo erano in passato
+        if !entry.kind.has_password() && entry.history.is_empty() {
+            return;
+        }
+
+        ui.label("🕘 Password precedenti");
+
+        if entry.history.is_empty() {
+            ui.small("Nessuna password precedente");
+            return;
+        }
+
+        let mut restore_index: Option<usize> = None;
+
+        for (index, previous) in entry.history.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.small(format!("Sostituita {}", format_age(previous.replaced_at)));
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("♻").on_hover_text("Ripristina questa password").clicked() {
+                        restore_index = Some(index);
+                    }
+
+                    if ui.small_button("📋").on_hover_text("Copia password").clicked() {
+                        let Some(key) = &self.encryption_key else {
+                            self.message = "Chiave di crittografia non disponibile!".to_string();
+                            self.message_color = egui::Color32::RED;
+                            return;
+                        };
+
+                        match decrypt_previous_password(entry, previous, key, self.app_data.vault_id()) {
+                            Ok(password) => {
+                                ctx.copy_text(password.to_string());
+                                self.message = format!("Password precedente di '{}' copiata!", entry.name);
+                                self.message_color = egui::Color32::GREEN;
+                            }
+                            Err(_) => {
+                                self.message = "Errore nella decrittografia!".to_string();
+                                self.message_color = egui::Color32::RED;
+                            }
+                        }
+                    }
+                });
+            });
+        }
+
+        if let Some(index) = restore_index {
+            self.restore_previous_password(&entry.id, index);
+        }
+    }
+
+    // I campi segreti restano mascherati finché non vengono mostrati o copiati
+    fn show_field_row(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        entry: &PasswordEntry,
+        field: &CustomField,
+        label: &str,
+    ) {
+        let shown_key = format!("{}/{}", entry.id, field.id);
+        ui.horizontal(|ui| {
+            ui.strong(label);
+            if !field.secret {
+                ui.label(&field.value);
+            } else if let Some((value, _)) = self.shown_passwords.get(&shown_key) {
+                ui.colored_label(egui::Color32::YELLOW, value.as_str());
+            } else {
+                ui.weak("••••••••");
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("📋").on_hover_text("Copia valore").clicked() {
+                    self.copy_field(ctx, entry, field, label);
+                }
+
+                if field.secret && ui.small_button("🔓").on_hover_text("Mostra").clicked() {
+                    if self.shown_passwords.remove(&shown_key).is_some() {
+                        return;
+                    }
+                    let Some(key) = &self.encryption_key else {
+                        return;
+                    };
+                    match decrypt_field(entry, field, key, self.app_data.vault_id()) {
+                        Ok(value) => {
+                            self.shown_passwords.insert(shown_key, (value, Instant::now()));
+                            self.mark_used(&entry.id);
+                        }
+                        Err(_) => {
+                            self.message = "Errore nella decrittografia!".to_string();
+                            self.message_color = egui::Color32::RED;
+                        }
+                    }
+                }
+            });
+        });
+    }
+
+    pub fn copy_field(&mut self, ctx: &egui::Context, entry: &PasswordEntry, field: &CustomField, label: &str) {
+        let Some(key) = &self.encryption_key else {
+            self.message = "Chiave di crittografia non disponibile!".to_string();
+            self.message_color = egui::Color32::RED;
+            return;
+        };
+
+        match decrypt_field(entry, field, key, self.app_data.vault_id()) {
+            Ok(value) => {
+                ctx.copy_text(value.to_string());
+                self.message = format!("'{}' di '{}' copiato!", label, entry.name);
+                self.message_color = egui::Color32::GREEN;
+                self.mark_used(&entry.id);
+            }
+            Err(_) => {
+                self.message = "Errore nella decrittografia!".to_string();
+                self.message_color = egui::Color32::RED;
+            }
+        }
+    }
+}
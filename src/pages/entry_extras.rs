@@ -0,0 +1,164 @@
+use crate::helpers::otp::{OtpAlgorithm, OtpKind};
+use crate::helpers::utils::{EntryExtras, EntryKind, FieldDraft, OtpDraft};
+use eframe::egui;
+
+// Sezione comune ai pannelli di aggiunta e modifica
+pub fn show_extras_editor(ui: &mut egui::Ui, extras: &mut EntryExtras, id_salt: &str) {
+    egui::CollapsingHeader::new("📎 Altri dettagli")
+        .id_salt(id_salt)
+        .show(ui, |ui| {
+            ui.label("🌐 Siti web (uno per riga)");
+            ui.add(
+                egui::TextEdit::multiline(&mut extras.urls)
+                    .hint_text("https://...")
+                    .desired_rows(2)
+                    .desired_width(230.0),
+            );
+            ui.add_space(10.0);
+
+            ui.label("🏷 Tag (separati da virgola)");
+            ui.add(
+                egui::TextEdit::singleline(&mut extras.tags)
+                    .hint_text("es. lavoro, banca")
+                    .min_size(egui::vec2(230.0, 25.0)),
+            );
+            ui.add_space(10.0);
+
+            ui.label("📝 Note");
+            ui.add(
+                egui::TextEdit::multiline(&mut extras.notes)
+                    .desired_rows(3)
+                    .desired_width(230.0),
+            );
+            ui.add_space(10.0);
+
+            show_otp_editor(ui, &mut extras.otp, id_salt);
+            ui.add_space(10.0);
+
+            ui.label("🧩 Campi personalizzati");
+            let mut remove_index: Option<usize> = None;
+            for (index, field) in extras.fields.iter_mut().enumerate() {
+                ui.push_id(&field.id, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut field.name)
+                                .hint_text("Nome")
+                                .desired_width(80.0),
+                        );
+                        // I campi segreti vengono mascherati e cifrati come la password
+                        ui.add(
+                            egui::TextEdit::singleline(&mut *field.value)
+                                .password(field.secret)
+                                .hint_text("Valore")
+                                .desired_width(100.0),
+                        );
+                        ui.checkbox(&mut field.secret, "🔒")
+                            .on_hover_text("Campo segreto");
+                        if ui.small_button("🗑").on_hover_text("Rimuovi campo").clicked() {
+                            remove_index = Some(index);
+                        }
+                    });
+                });
+            }
+            if let Some(index) = remove_index {
+                extras.fields.remove(index);
+            }
+
+            if ui.button("➕ Aggiungi campo").clicked() {
+                extras.fields.push(FieldDraft::new());
+            }
+        });
+}
+
+fn show_otp_editor(ui: &mut egui::Ui, otp: &mut OtpDraft, id_salt: &str) {
+    ui.label("🔢 Codice OTP (segreto o URI otpauth://)");
+    ui.add(
+        egui::TextEdit::singleline(&mut *otp.secret)
+            .password(true)
+            .hint_text("Lascia vuoto per nessun codice")
+            .min_size(egui::vec2(230.0, 25.0)),
+    );
+    // Un URI incollato viene subito scomposto in segreto e parametri
+    if let Err(e) = otp.import_uri() {
+        ui.colored_label(egui::Color32::RED, format!("⚠ {}", e));
+    }
+
+    if otp.secret.is_empty() {
+        return;
+    }
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(format!("{}_otp_kind", id_salt))
+            .selected_text(otp.kind.label())
+            .width(100.0)
+            .show_ui(ui, |ui| {
+                for kind in OtpKind::ALL {
+                    ui.selectable_value(&mut otp.kind, kind, kind.label());
+                }
+            });
+
+        // Steam Guard ha parametri fissi
+        if otp.kind == OtpKind::Steam {
+            return;
+        }
+        egui::ComboBox::from_id_salt(format!("{}_otp_algorithm", id_salt))
+            .selected_text(otp.algorithm.label())
+            .width(80.0)
+            .show_ui(ui, |ui| {
+                for algorithm in OtpAlgorithm::ALL {
+                    ui.selectable_value(&mut otp.algorithm, algorithm, algorithm.label());
+                }
+            });
+    });
+
+    if otp.kind == OtpKind::Steam {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut otp.digits, 6, "6 cifre");
+        ui.radio_value(&mut otp.digits, 8, "8 cifre");
+        if otp.kind == OtpKind::Hotp {
+            ui.add(egui::DragValue::new(&mut otp.counter).prefix("# "))
+                .on_hover_text("Contatore del prossimo codice");
+        } else {
+            ui.add(egui::DragValue::new(&mut otp.period).range(1..=300).suffix(" s"))
+                .on_hover_text("Durata di ogni codice");
+        }
+    });
+}
+
+pub fn show_kind_selector(ui: &mut egui::Ui, extras: &mut EntryExtras, id_salt: &str) {
+    let mut kind = extras.kind;
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(kind.label())
+        .width(230.0)
+        .show_ui(ui, |ui| {
+            for option in EntryKind::ALL {
+                ui.selectable_value(&mut kind, option, option.label());
+            }
+        });
+    if kind != extras.kind {
+        extras.set_kind(kind);
+    }
+}
+
+// Campi propri del tipo di entry, al posto di username e password
+pub fn show_typed_fields_editor(ui: &mut egui::Ui, extras: &mut EntryExtras) {
+    for (draft, field) in extras.typed.iter_mut().zip(extras.kind.fields()) {
+        ui.label(field.label);
+        if field.multiline {
+            ui.add(
+                egui::TextEdit::multiline(&mut *draft.value)
+                    .code_editor()
+                    .desired_rows(4)
+                    .desired_width(230.0),
+            );
+        } else {
+            ui.add(
+                egui::TextEdit::singleline(&mut *draft.value)
+                    .password(field.secret)
+                    .min_size(egui::vec2(230.0, 25.0)),
+            );
+        }
+        ui.add_space(10.0);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::PasswordManagerApp;
+use crate::helpers::utils::AppData;
+use eframe::egui;
+
+// Contenuto trascinato dalla lista password verso una cartella
+pub struct DraggedEntry(pub String);
+
+impl PasswordManagerApp {
+    pub fn show_folder_tree(&mut self, ui: &mut egui::Ui) {
+        ui.strong("📁 Cartelle");
+        ui.add_space(10.0);
+
+        let mut dropped: Option<(String, Option<String>)> = None;
+
+        egui::ScrollArea::vertical()
+            .id_salt("folder_tree_scroll")
+            .auto_shrink([false, true])
+            .max_height((ui.available_height() - 110.0).max(100.0))
+            .show(ui, |ui| {
+                let all_count = self.app_data.ps.len();
+                if let Some(entry_id) = self.folder_row(ui, None, &format!("🗂 Tutte ({})", all_count), 0) {
+                    dropped = Some((entry_id, None));
+                }
+
+                for (id, name, depth) in tree_rows(&self.app_data) {
+                    let count = self.app_data.ps.iter().filter(|entry| entry.folder.as_ref() == Some(&id)).count();
+                    let label = format!("📁 {} ({})", name, count);
+                    if let Some(entry_id) = self.folder_row(ui, Some(&id), &label, depth) {
+                        dropped = Some((entry_id, Some(id)));
+                    }
+                }
+            });
+
+        ui.add_space(10.0);
+        ui.add(
+            egui::TextEdit::singleline(&mut self.folder_name_input)
+                .hint_text("Nome cartella")
+                .desired_width(180.0),
+        );
+        ui.horizontal(|ui| {
+            let hint = if self.selected_folder.is_some() {
+                "Nuova sottocartella"
+            } else {
+                "Nuova cartella"
+            };
+            if ui.button("➕").on_hover_text(hint).clicked() {
+                self.create_folder();
+            }
+
+            if let Some(selected) = self.selected_folder.clone() {
+                if ui.button("✏").on_hover_text("Rinomina cartella").clicked() {
+                    self.rename_folder(&selected);
+                }
+                if ui.button("🗑").on_hover_text("Elimina cartella (le password passano alla cartella superiore)").clicked() {
+                    self.delete_folder(&selected);
+                }
+            }
+        });
+        ui.small("Trascina ✥ una password su una cartella per spostarla");
+
+        if let Some((entry_id, folder)) = dropped {
+            self.move_entry_to_folder(&entry_id, folder);
+        }
+    }
+
+    // Restituisce l'entry rilasciata sulla riga, se presente
+    fn folder_row(&mut self, ui: &mut egui::Ui, folder_id: Option<&str>, label: &str, depth: usize) -> Option<String> {
+        let selected = self.selected_folder.as_deref() == folder_id;
+        let response = ui
+            .horizontal(|ui| {
+                ui.add_space(depth as f32 * 12.0);
+                ui.selectable_label(selected, label)
+            })
+            .inner;
+
+        if response.clicked() {
+            self.selected_folder = folder_id.map(str::to_string);
+            // Le nuove password finiscono nella cartella che si sta guardando
+            self.new_entry_extras.folder = self.selected_folder.clone();
+        }
+
+        if response.dnd_hover_payload::<DraggedEntry>().is_some() {
+            ui.painter().rect_stroke(
+                response.rect,
+                4.0,
+                egui::Stroke::new(1.5, ui.visuals().selection.stroke.color),
+                egui::StrokeKind::Inside,
+            );
+        }
+
+        response
+            .dnd_release_payload::<DraggedEntry>()
+            .map(|dragged| dragged.0.clone())
+    }
+}
+
+// Cartelle in ordine di visita, con la profondità per l'indentazione
+fn tree_rows(data: &AppData) -> Vec<(String, String, usize)> {
+    let mut rows = Vec::new();
+    let mut stack: Vec<(String, usize)> = data
+        .child_folders(None)
+        .into_iter()
+        .rev()
+        .map(|folder| (folder.id.clone(), 0))
+        .collect();
+
+    while let Some((id, depth)) = stack.pop() {
+        let Some(folder) = data.folder(&id) else {
+            continue;
+        };
+        rows.push((folder.id.clone(), folder.name.clone(), depth));
+        stack.extend(
+            data.child_folders(Some(&id))
+                .into_iter()
+                .rev()
+                .map(|child| (child.id.clone(), depth + 1)),
+        );
+    }
+    rows
+}
+
+// Scelta della cartella nei pannelli di aggiunta e modifica
+pub fn folder_combo(ui: &mut egui::Ui, data: &AppData, folder: &mut Option<String>, id_salt: &str) {
+    let selected_text = folder
+        .as_deref()
+        .filter(|id| data.folder(id).is_some())
+        .map_or("Nessuna cartella".to_string(), |id| data.folder_path(id));
+
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected_text)
+        .width(230.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(folder, None, "Nessuna cartella");
+            for (id, name, depth) in tree_rows(data) {
+                let label = format!("{}📁 {}", "   ".repeat(depth), name);
+                ui.selectable_value(folder, Some(id), label);
+            }
+        });
+}
@@ -0,0 +1,107 @@
+use crate::helpers::generate_password::{
+    DEFAULT_SYMBOLS, GeneratorMode, GeneratorOptions, MAX_LENGTH, MAX_WORDS, MIN_LENGTH, MIN_WORDS,
+};
+use crate::helpers::utils::AppData;
+use eframe::egui;
+
+// Opzioni del generatore nel popup dei pannelli di aggiunta e modifica
+pub fn show_generator_options(ui: &mut egui::Ui, options: &mut GeneratorOptions, id_salt: &str) {
+    egui::CollapsingHeader::new("⚙ Opzioni")
+        .id_salt(id_salt)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut options.mode, GeneratorMode::Password, "🔤 Password");
+                ui.radio_value(&mut options.mode, GeneratorMode::Passphrase, "📖 Passphrase");
+            });
+
+            if options.mode == GeneratorMode::Passphrase {
+                show_passphrase_options(ui, options);
+            } else {
+                show_password_options(ui, options);
+            }
+
+            if let Err(e) = options.validate() {
+                ui.colored_label(egui::Color32::RED, format!("⚠ {}", e));
+            }
+        });
+}
+
+fn show_password_options(ui: &mut egui::Ui, options: &mut GeneratorOptions) {
+    ui.horizontal(|ui| {
+        ui.label("Lunghezza");
+        ui.add(egui::Slider::new(&mut options.length, MIN_LENGTH..=MAX_LENGTH));
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut options.uppercase, "A-Z");
+        ui.checkbox(&mut options.lowercase, "a-z");
+        ui.checkbox(&mut options.digits, "0-9");
+        ui.checkbox(&mut options.symbols, "Simboli");
+    });
+
+    if options.symbols {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut options.symbol_set)
+                    .hint_text("Simboli ammessi")
+                    .desired_width(170.0),
+            );
+            if ui.small_button("↺").on_hover_text("Simboli predefiniti").clicked() {
+                options.symbol_set = DEFAULT_SYMBOLS.to_string();
+            }
+        });
+    }
+
+    ui.checkbox(&mut options.exclude_ambiguous, "Escludi caratteri ambigui (0 O 1 l I)");
+
+    ui.horizontal(|ui| {
+        ui.label("Minimo per tipo");
+        ui.add(egui::DragValue::new(&mut options.min_per_class).range(0..=MAX_LENGTH));
+    });
+}
+
+fn show_passphrase_options(ui: &mut egui::Ui, options: &mut GeneratorOptions) {
+    let passphrase = &mut options.passphrase;
+    ui.horizontal(|ui| {
+        ui.label("Parole");
+        ui.add(egui::Slider::new(&mut passphrase.words, MIN_WORDS..=MAX_WORDS));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Separatore");
+        ui.add(egui::TextEdit::singleline(&mut passphrase.separator).desired_width(40.0));
+    });
+
+    ui.checkbox(&mut passphrase.capitalize, "Iniziali maiuscole");
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut passphrase.add_digit, "Aggiungi una cifra");
+        ui.checkbox(&mut passphrase.add_symbol, "Aggiungi un simbolo");
+    });
+
+    if passphrase.add_symbol {
+        ui.add(
+            egui::TextEdit::singleline(&mut options.symbol_set)
+                .hint_text("Simboli ammessi")
+                .desired_width(170.0),
+        );
+    }
+}
+
+// Scelta del profilo nei pannelli di aggiunta e modifica
+pub fn profile_combo(ui: &mut egui::Ui, data: &AppData, profile: &mut Option<String>, id_salt: &str) {
+    let selected_text = profile
+        .as_deref()
+        .and_then(|id| data.profile(id))
+        .map_or("Nessun profilo".to_string(), |profile| profile.name.clone());
+
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected_text)
+        .width(230.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(profile, None, "Nessun profilo");
+            for option in &data.profiles {
+                ui.selectable_value(profile, Some(option.id.clone()), &option.name)
+                    .on_hover_text(option.options.summary());
+            }
+        });
+}
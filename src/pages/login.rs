@@ -1,8 +1,15 @@
 use crate::PasswordManagerApp;
+use crate::helpers::vault::{list_backups, restore_backup};
 use eframe::egui;
+use std::time::SystemTime;
 
 impl PasswordManagerApp {
     pub fn show_login(&mut self, ui: &mut egui::Ui) {
+        if let Some(load_error) = self.load_error.clone() {
+            self.show_load_error(ui, &load_error.to_string());
+            return;
+        }
+
         ui.vertical_centered(|ui| {
             ui.add_space(60.0);
 
@@ -35,7 +42,7 @@ impl PasswordManagerApp {
 
                                 ui.label("🔑 Password:");
                                 ui.add(
-                                    egui::TextEdit::singleline(&mut self.login_password)
+                                    egui::TextEdit::singleline(&mut *self.login_password)
                                         .password(!self.show_password)
                                         .desired_width(200.0),
                                 );
@@ -57,4 +64,69 @@ impl PasswordManagerApp {
                 });
         });
     }
+
+    // Il vault esiste ma non è leggibile: niente registrazione, il file resta intatto
+    fn show_load_error(&mut self, ui: &mut egui::Ui, load_error: &str) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(60.0);
+
+            ui.heading("Impossibile aprire il vault");
+            ui.add_space(10.0);
+            ui.label("Il file dei dati non è stato modificato.");
+            ui.add_space(40.0);
+
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(8.0)
+                .inner_margin(20.0)
+                .show(ui, |ui| {
+                    ui.set_max_width(400.0);
+
+                    ui.vertical_centered_justified(|ui| {
+                        ui.colored_label(egui::Color32::RED, format!("⚠ {}", load_error));
+                        ui.add_space(20.0);
+
+                        if ui
+                            .add_sized([100.0, 35.0], egui::Button::new("🔄 Riprova"))
+                            .clicked()
+                        {
+                            self.reload_vault();
+                        }
+
+                        let backups = list_backups();
+                        if !backups.is_empty() {
+                            ui.add_space(20.0);
+                            ui.separator();
+                            ui.label("🗄 Ripristina da un backup");
+                            ui.add_space(10.0);
+
+                            for (path, modified) in backups {
+                                let name = path
+                                    .file_name()
+                                    .map(|name| name.to_string_lossy().to_string())
+                                    .unwrap_or_default();
+                                let age = modified
+                                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                                    .map(|age| format!(" ({} min fa)", age.as_secs() / 60))
+                                    .unwrap_or_default();
+
+                                if ui.button(format!("{}{}", name, age)).clicked() {
+                                    match restore_backup(&path) {
+                                        Ok(()) => {
+                                            self.reload_vault();
+                                            self.message = format!("Backup '{}' ripristinato!", name);
+                                            self.message_color = egui::Color32::GREEN;
+                                        }
+                                        Err(e) => {
+                                            self.message = format!("Ripristino non riuscito! {}", e);
+                                            self.message_color = egui::Color32::RED;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    });
+                });
+        });
+    }
 }
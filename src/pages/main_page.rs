@@ -16,6 +16,7 @@ impl PasswordManagerApp {
                     ui.horizontal(|ui| {
                         ui.selectable_value(&mut self.active_tab, 0, "➕ Aggiungi");
                         ui.selectable_value(&mut self.active_tab, 1, "⚙ Modifica");
+                        ui.selectable_value(&mut self.active_tab, 2, "🔧 Impostazioni");
                     });
 
                     ui.add_space(10.0);
@@ -27,12 +28,22 @@ impl PasswordManagerApp {
                         .show(ui, |ui| match self.active_tab {
                             0 => self.show_add_password_panel(ui),
                             1 => self.show_edit_password_panel(ui),
+                            2 => self.show_settings_panel(ui),
                             _ => {}
                         });
                 });
 
                 ui.separator();
 
+                // Albero delle cartelle
+                ui.vertical(|ui| {
+                    ui.set_min_width(200.0);
+                    ui.set_max_width(200.0);
+                    self.show_folder_tree(ui);
+                });
+
+                ui.separator();
+
                 // Pannello a destra (lista password)
                 self.show_password_list(ctx, ui);
             },
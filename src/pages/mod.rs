@@ -3,4 +3,10 @@ mod login;
 mod registration;
 mod add_password;
 mod modify_password;
-mod password_list;
\ No newline at end of file
+mod password_list;
+mod settings;
+mod trash;
+mod entry_details;
+mod entry_extras;
+mod folders;
+mod generator;
@@ -1,5 +1,9 @@
 use crate::PasswordManagerApp;
+use crate::PasswordEntry;
 use crate::helpers::generate_password::generate_password;
+use super::entry_extras::{show_extras_editor, show_kind_selector, show_typed_fields_editor};
+use super::folders::folder_combo;
+use super::generator::{profile_combo, show_generator_options};
 use eframe::egui;
 
 impl PasswordManagerApp {
@@ -16,102 +20,210 @@ impl PasswordManagerApp {
 
                         ui.vertical(|ui| {
                             ui.label("🎯 Servizio da modificare");
-                            ui.add(
-                                egui::TextEdit::singleline(&mut self.edit_service_name)
-                                    .hint_text("Nome del servizio esistente")
-                                    .min_size(egui::vec2(230.0, 25.0)),
-                            );
+
+                            // Nome e username distinguono le voci con lo stesso nome
+                            let selected_text = self
+                                .edit_entry_id
+                                .as_deref()
+                                .and_then(|id| self.app_data.entry(id))
+                                .map_or("Seleziona una password".to_string(), entry_label);
+                            let mut selected_id = self.edit_entry_id.clone();
+                            egui::ComboBox::from_id_salt("edit_entry_select")
+                                .selected_text(selected_text)
+                                .width(230.0)
+                                .show_ui(ui, |ui| {
+                                    for entry in &self.app_data.ps {
+                                        ui.selectable_value(
+                                            &mut selected_id,
+                                            Some(entry.id.clone()),
+                                            entry_label(entry),
+                                        );
+                                    }
+                                });
+                            if selected_id != self.edit_entry_id
+                                && let Some(id) = selected_id
+                            {
+                                self.start_editing(&id);
+                            }
+                            ui.add_space(10.0);
+
+                            if self.edit_entry_id.is_none() {
+                                ui.small("Scegli una voce qui sopra o premi ✏ su una password della lista");
+                                return;
+                            }
+
+                            ui.label("🗂 Tipo");
+                            show_kind_selector(ui, &mut self.edit_entry_extras, "edit_entry_kind");
                             ui.add_space(10.0);
 
-                            ui.label("👤 Nuovo username (opzionale)");
+                            ui.label("🏷 Nome servizio");
                             ui.add(
-                                egui::TextEdit::singleline(&mut self.edit_new_username)
-                                    .hint_text("Lascia vuoto per non modificare")
+                                egui::TextEdit::singleline(&mut self.edit_entry_name)
+                                    .hint_text("es. Gmail, Facebook...")
                                     .min_size(egui::vec2(230.0, 25.0)),
                             );
                             ui.add_space(10.0);
 
-                            ui.label("🔑 Nuova password");
+                            if self.edit_entry_extras.kind.has_password() {
+                                ui.label("👤 Username");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.edit_entry_username)
+                                        .hint_text("username o email")
+                                        .min_size(egui::vec2(230.0, 25.0)),
+                                );
+                                ui.add_space(10.0);
+
+                                ui.label("🔑 Password");
                             
-                            let password_response = ui.add(
-                                egui::TextEdit::singleline(&mut self.edit_new_password)
-                                    .password(!self.show_password)
-                                    .hint_text("Nuova password sicura")
-                                    .min_size(egui::vec2(230.0, 25.0)),
-                            );
+                                let (password_response, dice_response) = ui
+                                    .horizontal(|ui| {
+                                        let password_response = ui.add(
+                                            egui::TextEdit::singleline(&mut *self.edit_new_password)
+                                                .password(!self.show_password)
+                                                .hint_text("password sicura")
+                                                .min_size(egui::vec2(230.0, 25.0)),
+                                        );
+                                        let dice_response = ui.button("🎲").on_hover_text("Genera una nuova password");
+                                        (password_response, dice_response)
+                                    })
+                                    .inner;
                             
-                            // Quando il field password è vuoto e prende il focus mostra il popup
-                            if password_response.gained_focus() && self.edit_new_password.is_empty() {
-                                self.show_popup_edit = true;
-                            }
+                                // Quando il field password è vuoto e prende il focus mostra il popup
+                                if password_response.gained_focus() && self.edit_new_password.is_empty() {
+                                    self.show_popup_edit = true;
+                                }
+                                // Il campo è già compilato: il popup si apre dal pulsante
+                                if dice_response.clicked() {
+                                    self.show_popup_edit = !self.show_popup_edit;
+                                }
                             
-                            // Suggerisci password
-                            if self.show_popup_edit {
-                                let popup_id = ui.make_persistent_id("password_gen_popup_edit");
-                                egui::Area::new(popup_id)
-                                    .fixed_pos(password_response.rect.left_bottom() + egui::vec2(0.0, 5.0))
-                                    .show(ui.ctx(), |ui| {
-                                        egui::Frame::popup(ui.style())
-                                            .show(ui, |ui| {
-                                                ui.set_min_width(230.0);
-                                                ui.vertical(|ui| {
-                                                    ui.label("🎲 Vuoi generare una password sicura?");
-                                                    ui.add_space(8.0);
-                                                    
-                                                    ui.horizontal(|ui| {
-                                                        if ui.button("✅ Genera").clicked() {
-                                                            let p = generate_password();
-                                                            self.edit_new_password = p.clone();
-                                                            self.edit_confirm_password = p;
-                                                            self.show_popup_edit = false;
+                                // Suggerisci password
+                                if self.show_popup_edit {
+                                    let popup_id = ui.make_persistent_id("password_gen_popup_edit");
+                                    egui::Area::new(popup_id)
+                                        .fixed_pos(password_response.rect.left_bottom() + egui::vec2(0.0, 5.0))
+                                        .show(ui.ctx(), |ui| {
+                                            egui::Frame::popup(ui.style())
+                                                .show(ui, |ui| {
+                                                    ui.set_min_width(230.0);
+                                                    ui.vertical(|ui| {
+                                                        ui.label("🎲 Vuoi generare una password sicura?");
+                                                        ui.add_space(8.0);
+                                                        // Con un profilo associato valgono le sue regole
+                                                        let profile = self
+                                                            .edit_entry_extras
+                                                            .generator_profile
+                                                            .as_deref()
+                                                            .and_then(|id| self.app_data.profile(id))
+                                                            .cloned();
+                                                        match &profile {
+                                                            Some(profile) => {
+                                                                ui.small(format!("📐 {} • {}", profile.name, profile.options.summary()));
+                                                            }
+                                                            None => show_generator_options(ui, &mut self.generator_options, "edit_generator_options"),
                                                         }
+                                                        ui.add_space(8.0);
+                                                    
+                                                        ui.horizontal(|ui| {
+                                                            if ui.button("✅ Genera").clicked() {
+                                                                let options = profile.as_ref().map_or(&self.generator_options, |profile| &profile.options);
+                                                                match generate_password(options) {
+                                                                    Ok(p) => {
+                                                                        // Nei buffer esistenti, che hanno già la capacità per le modifiche
+                                                                        self.edit_new_password.clear();
+                                                                        self.edit_new_password.push_str(&p);
+                                                                        self.edit_confirm_password.clear();
+                                                                        self.edit_confirm_password.push_str(&p);
+                                                                        self.show_popup_edit = false;
+                                                                    }
+                                                                    Err(e) => {
+                                                                        self.message = e;
+                                                                        self.message_color = egui::Color32::RED;
+                                                                    }
+                                                                }
+                                                            }
                                                         
-                                                        if ui.button("❌ No grazie").clicked() {
-                                                            self.show_popup_edit = false;
-                                                        }
+                                                            if ui.button("❌ No grazie").clicked() {
+                                                                self.show_popup_edit = false;
+                                                            }
+                                                        });
                                                     });
                                                 });
-                                            });
-                                    });
-                            }
+                                        });
+                                }
                             
-                            if self.show_popup_edit && ui.input(|i| i.pointer.any_click()) {
-                                let popup_id = ui.make_persistent_id("password_gen_popup_edit");
-                                if let Some(area_response) = ui.ctx().memory(|mem| {
-                                    mem.area_rect(popup_id)
-                                }) {
-                                    if let Some(pointer_pos) = ui.input(|i| i.pointer.interact_pos()) {
-                                        if !area_response.contains(pointer_pos) && !password_response.rect.contains(pointer_pos) {
-                                            self.show_popup_edit = false;
-                                        }
+                                if self.show_popup_edit && ui.input(|i| i.pointer.any_click()) {
+                                    let popup_id = ui.make_persistent_id("password_gen_popup_edit");
+                                    if let Some(area_response) = ui.ctx().memory(|mem| {
+                                        mem.area_rect(popup_id)
+                                    }) && let Some(pointer_pos) = ui.input(|i| i.pointer.interact_pos())
+                                        && !area_response.contains(pointer_pos) && !password_response.rect.contains(pointer_pos)
+                                        && !dice_response.rect.contains(pointer_pos) {
+                                        self.show_popup_edit = false;
                                     }
                                 }
+
+                                ui.checkbox(&mut self.show_password, "Mostra");
+                                ui.add_space(10.0);
+
+                                ui.label("🔑 Conferma password");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut *self.edit_confirm_password)
+                                        .password(!self.show_password1)
+                                        .hint_text("Ripeti la password")
+                                        .min_size(egui::vec2(230.0, 25.0)),
+                                );
+                                ui.checkbox(&mut self.show_password1, "Mostra");
+                                ui.add_space(10.0);
+
+                                ui.label("📐 Profilo generatore");
+                                profile_combo(ui, &self.app_data, &mut self.edit_entry_extras.generator_profile, "edit_entry_profile");
+                            } else {
+                                show_typed_fields_editor(ui, &mut self.edit_entry_extras);
                             }
+                            ui.add_space(10.0);
 
-                            ui.checkbox(&mut self.show_password, "Mostra");
+                            ui.label("📁 Cartella");
+                            folder_combo(ui, &self.app_data, &mut self.edit_entry_extras.folder, "edit_entry_folder");
                             ui.add_space(10.0);
 
-                            ui.label("🔑 Conferma password");
-                            ui.add(
-                                egui::TextEdit::singleline(&mut self.edit_confirm_password)
-                                    .password(!self.show_password1)
-                                    .hint_text("Ripeti la nuova password")
-                                    .min_size(egui::vec2(230.0, 25.0)),
-                            );
-                            ui.checkbox(&mut self.show_password1, "Mostra");
+                            show_extras_editor(ui, &mut self.edit_entry_extras, "edit_entry_extras");
                             ui.add_space(15.0);
                         });
 
-                        if ui
-                            .add_sized([230.0, 35.0], egui::Button::new("🔄 Modifica Password"))
-                            .clicked()
-                        {
-                            self.show_password = false;
-                            self.show_password1 = false;
-                            self.edit_password();
+                        if self.edit_entry_id.is_none() {
+                            return;
                         }
+
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_sized([150.0, 35.0], egui::Button::new("💾 Salva Modifiche"))
+                                .clicked()
+                            {
+                                self.show_password = false;
+                                self.show_password1 = false;
+                                self.edit_password();
+                            }
+
+                            if ui
+                                .add_sized([75.0, 35.0], egui::Button::new("❌ Annulla"))
+                                .clicked()
+                            {
+                                self.show_password = false;
+                                self.show_password1 = false;
+                                self.cancel_editing();
+                            }
+                        });
                     });
                 });
         });
     }
-}
\ No newline at end of file
+}
+
+fn entry_label(entry: &PasswordEntry) -> String {
+    if entry.u.is_empty() {
+        entry.name.clone()
+    } else {
+        format!("{} • {}", entry.name, entry.u)
+    }
+}
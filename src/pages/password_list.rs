@@ -1,37 +1,62 @@
 use std::time::Instant;
+use zeroize::Zeroizing;
 use eframe::egui;
-use crate::PasswordManagerApp;
+use crate::{ListSort, PasswordManagerApp};
 use crate::PasswordEntry;
+use crate::helpers::otp::current_code;
 use crate::helpers::utils::*;
+use super::folders::DraggedEntry;
 
 impl PasswordManagerApp {
     pub fn show_password_list(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if self.show_trash {
+            self.show_trash_list(ui);
+            return;
+        }
+
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
                 ui.strong("📃 Le tue Password");
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .button(format!("🗑 {}", self.app_data.trash.len()))
+                        .on_hover_text("Cestino")
+                        .clicked()
+                    {
+                        self.show_trash = true;
+                    }
                     if ui.button("❌").on_hover_text("Cancella ricerca").clicked() {
                         self.search_query.clear();
                     }
                     ui.add(egui::TextEdit::singleline(&mut self.search_query)
                         .hint_text("🔍 Cerca...")
                         .desired_width(150.0));
+
+                    egui::ComboBox::from_id_salt("list_sort")
+                        .selected_text(self.list_sort.label())
+                        .show_ui(ui, |ui| {
+                            for sort in ListSort::ALL {
+                                ui.selectable_value(&mut self.list_sort, sort, sort.label());
+                            }
+                        });
                 });
             });
             
             ui.add_space(10.0);
             
-            let filtered_entries: Vec<(usize, &PasswordEntry)> = self.app_data.ps
+            // La cartella selezionata include le sue sottocartelle, anche durante la ricerca
+            let folder_filter = self
+                .selected_folder
+                .as_deref()
+                .map(|folder| self.app_data.folder_subtree(folder));
+
+            let filtered_entries: Vec<&PasswordEntry> = self.app_data.ps
                 .iter()
-                .enumerate()
-                .filter(|(_, entry)| {
-                    if self.search_query.is_empty() {
-                        true
-                    } else {
-                        entry.name.to_lowercase().contains(&self.search_query.to_lowercase()) ||
-                        entry.u.to_lowercase().contains(&self.search_query.to_lowercase())
-                    }
+                .filter(|entry| match &folder_filter {
+                    Some(subtree) => entry.folder.as_ref().is_some_and(|folder| subtree.contains(folder)),
+                    None => true,
                 })
+                .filter(|entry| self.search_query.is_empty() || self.app_data.entry_matches(entry, &self.search_query))
                 .collect();
             
             if !self.search_query.is_empty() && !filtered_entries.is_empty() {
@@ -46,7 +71,9 @@ impl PasswordManagerApp {
                     remaining_space,
                     egui::Layout::centered_and_justified(egui::Direction::TopDown),
                     |ui| {
-                        if self.search_query.is_empty() {
+                        if self.search_query.is_empty() && self.selected_folder.is_some() {
+                            ui.label("📭 Nessuna password in questa cartella");
+                        } else if self.search_query.is_empty() {
                             ui.label("📭 Nessuna password salvata");
                             ui.small("Aggiungi la tua prima password usando il pannello a sinistra");
                         } else {
@@ -56,12 +83,14 @@ impl PasswordManagerApp {
                     }
                 );
             } else {
-                let mut remove_indices = Vec::new();
+                let mut remove_ids: Vec<String> = Vec::new();
+                let mut edit_id: Option<String> = None;
                 
-                let entries_to_show: Vec<(usize, PasswordEntry)> = filtered_entries
+                let mut entries_to_show: Vec<PasswordEntry> = filtered_entries
                     .into_iter()
-                    .map(|(index, entry)| (index, entry.clone()))
+                    .cloned()
                     .collect();
+                self.list_sort.sort(&mut entries_to_show);
                 
                 // Sezione mostra password
                 egui::ScrollArea::vertical()
@@ -70,8 +99,8 @@ impl PasswordManagerApp {
                     .min_scrolled_height(remaining_space.y)
                     .max_height(remaining_space.y)
                     .show(ui, |ui| {
-                        for (index, entry_clone) in entries_to_show {
-                            ui.push_id(format!("password_entry_{}", index), |ui| {
+                        for entry_clone in entries_to_show {
+                            ui.push_id(format!("password_entry_{}", entry_clone.id), |ui| {
                                 egui::Frame::new()
                                     .fill(ui.visuals().window_fill)
                                     .corner_radius(6.0)
@@ -79,22 +108,61 @@ impl PasswordManagerApp {
                                     .stroke(egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color))
                                     .show(ui, |ui| {
                                         ui.horizontal(|ui| {
+                                            ui.dnd_drag_source(
+                                                egui::Id::new(("drag_entry", &entry_clone.id)),
+                                                DraggedEntry(entry_clone.id.clone()),
+                                                |ui| {
+                                                    ui.label("✥");
+                                                },
+                                            )
+                                            .response
+                                            .on_hover_text("Trascina in una cartella");
+
                                             ui.vertical(|ui| {
+                                                let is_login = entry_clone.kind.has_password();
                                                 ui.horizontal(|ui| {
                                                     ui.strong(&entry_clone.name);
                                                     ui.label("•");
-                                                    ui.weak(&entry_clone.u);
+                                                    if is_login {
+                                                        ui.weak(&entry_clone.u);
+                                                    } else {
+                                                        ui.weak(entry_clone.kind.label());
+                                                    }
                                                 });
+
+                                                if let Some(folder) = &entry_clone.folder
+                                                    && self.app_data.folder(folder).is_some()
+                                                {
+                                                    ui.small(format!("📁 {}", self.app_data.folder_path(folder)));
+                                                }
+
+                                                if let Some(url) = entry_clone.urls.first() {
+                                                    ui.hyperlink_to(format!("🌐 {}", url), url);
+                                                }
+
+                                                if !entry_clone.attachments.is_empty() {
+                                                    ui.small(format!("📎 {} allegati", entry_clone.attachments.len()));
+                                                }
+
+                                                if !entry_clone.tags.is_empty() {
+                                                    ui.horizontal_wrapped(|ui| {
+                                                        for tag in &entry_clone.tags {
+                                                            ui.small(format!("🏷 {}", tag));
+                                                        }
+                                                    });
+                                                }
                                                 
-                                                if let Some((password, start_time)) = self.shown_passwords.get(&index) {
+                                                if !is_login {
+                                                    ui.small(typed_summary(&entry_clone));
+                                                } else if let Some((password, start_time)) = self.shown_passwords.get(&entry_clone.id) {
                                                     let remaining_time = 10 - start_time.elapsed().as_secs();
                                                     ui.horizontal(|ui| {
-                                                        ui.colored_label(egui::Color32::YELLOW, format!("🔓 {}", password));
+                                                        ui.colored_label(egui::Color32::YELLOW, format!("🔓 {}", password.as_str()));
                                                         ui.small(format!("({}s)", remaining_time));
                                                     });
                                                 } else {
                                                     if let Some(key) = &self.encryption_key {
-                                                        match decrypt_password(&entry_clone, key) {
+                                                        match decrypt_password(&entry_clone, key, self.app_data.vault_id()) {
                                                             Ok(_) => {
                                                                 ui.small("🔒 Password protetta");
                                                             }
@@ -106,63 +174,88 @@ impl PasswordManagerApp {
                                                         ui.colored_label(egui::Color32::RED, "⚠ Chiave non disponibile");
                                                     }
                                                 }
+
+                                                if let Some(otp) = &entry_clone.otp {
+                                                    self.show_otp_row(ctx, ui, &entry_clone, otp);
+                                                }
                                             });
                                             
                                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
 
-                                                if ui.button("🗑").on_hover_text("Elimina").clicked() {
-                                                    if confirm_notification() {
-                                                        remove_indices.push(index);
-                                                    }
+                                                if ui.button("🗑").on_hover_text("Sposta nel cestino").clicked() {
+                                                    remove_ids.push(entry_clone.id.clone());
+                                                }
+
+                                                if ui.button("✏").on_hover_text("Modifica").clicked() {
+                                                    edit_id = Some(entry_clone.id.clone());
+                                                }
+
+                                                let details_open = self.details_entry_id.as_ref() == Some(&entry_clone.id);
+                                                if ui.selectable_label(details_open, "ℹ").on_hover_text("Dettagli").clicked() {
+                                                    self.details_entry_id = if details_open { None } else { Some(entry_clone.id.clone()) };
                                                 }
                                                 
-                                                if ui.button("🔓").on_hover_text("Mostra Password").clicked() {
-                                                    if let Some(key) = &self.encryption_key {
-                                                        match decrypt_password(&entry_clone, key) {
-                                                            Ok(decrypted_password) => {
-                                                                if self.shown_passwords.contains_key(&index) {
-                                                                    self.shown_passwords.remove(&index);
-                                                                } else {
-                                                                    self.shown_passwords.insert(index, (decrypted_password, Instant::now()));
+                                                if entry_clone.kind.has_password() {
+                                                    if ui.button("🔓").on_hover_text("Mostra Password").clicked() {
+                                                        if let Some(key) = &self.encryption_key {
+                                                            match decrypt_password(&entry_clone, key, self.app_data.vault_id()) {
+                                                                Ok(decrypted_password) => {
+                                                                    if self.shown_passwords.remove(&entry_clone.id).is_none() {
+                                                                        self.shown_passwords.insert(entry_clone.id.clone(), (decrypted_password, Instant::now()));
+                                                                        self.mark_used(&entry_clone.id);
+                                                                    }
+                                                                }
+                                                                Err(_) => {
+                                                                    self.message = "Errore nella decrittografia!".to_string();
+                                                                    self.message_color = egui::Color32::RED;
                                                                 }
                                                             }
-                                                            Err(_) => {
-                                                                self.message = "Errore nella decrittografia!".to_string();
-                                                                self.message_color = egui::Color32::RED;
-                                                            }
+                                                        } else {
+                                                            self.message = "Chiave di crittografia non disponibile!".to_string();
+                                                            self.message_color = egui::Color32::RED;
                                                         }
-                                                    } else {
-                                                        self.message = "Chiave di crittografia non disponibile!".to_string();
-                                                        self.message_color = egui::Color32::RED;
                                                     }
-                                                }
 
-                                                if ui.button("📋").on_hover_text("Copia password").clicked() {
-                                                    if let Some(key) = &self.encryption_key {
-                                                        match decrypt_password(&entry_clone, key) {
-                                                            Ok(decrypted_password) => {
-                                                                ctx.copy_text(decrypted_password);
-                                                                self.message = format!("La password di '{}' è stata copiata!", entry_clone.name);
-                                                                self.message_color = egui::Color32::GREEN;
-                                                            }
-                                                            Err(_) => {
-                                                                self.message = "Errore nella decrittografia!".to_string();
-                                                                self.message_color = egui::Color32::RED;
+                                                    if ui.button("📋").on_hover_text("Copia password").clicked() {
+                                                        if let Some(key) = &self.encryption_key {
+                                                            match decrypt_password(&entry_clone, key, self.app_data.vault_id()) {
+                                                                Ok(decrypted_password) => {
+                                                                    // Il testo negli appunti non è più sotto il nostro controllo
+                                                                    ctx.copy_text(decrypted_password.to_string());
+                                                                    self.message = format!("La password di '{}' è stata copiata!", entry_clone.name);
+                                                                    self.message_color = egui::Color32::GREEN;
+                                                                    self.mark_used(&entry_clone.id);
+                                                                }
+                                                                Err(_) => {
+                                                                    self.message = "Errore nella decrittografia!".to_string();
+                                                                    self.message_color = egui::Color32::RED;
+                                                                }
                                                             }
+                                                        } else {
+                                                            self.message = "Chiave di crittografia non disponibile!".to_string();
+                                                            self.message_color = egui::Color32::RED;
                                                         }
-                                                    } else {
-                                                        self.message = "Chiave di crittografia non disponibile!".to_string();
-                                                        self.message_color = egui::Color32::RED;
                                                     }
-                                                }
                                                 
-                                                if ui.button("👤").on_hover_text("Copia username").clicked() {
-                                                    ctx.copy_text(entry_clone.u.clone());
-                                                    self.message = format!("L'username di '{}' è stato copiato!", entry_clone.name);
-                                                    self.message_color = egui::Color32::GREEN;
+                                                    if ui.button("👤").on_hover_text("Copia username").clicked() {
+                                                        ctx.copy_text(entry_clone.u.clone());
+                                                        self.message = format!("L'username di '{}' è stato copiato!", entry_clone.name);
+                                                        self.message_color = egui::Color32::GREEN;
+                                                        self.mark_used(&entry_clone.id);
+                                                    }
+                                                } else if let Some(primary) = entry_clone.kind.primary_field()
+                                                    && let Some(field) = entry_clone.data_field(primary.key)
+                                                    && ui.button("📋").on_hover_text(format!("Copia {}", primary.label.to_lowercase())).clicked()
+                                                {
+                                                    self.copy_field(ctx, &entry_clone, field, primary.label);
                                                 }
                                             });
                                         });
+
+                                        if self.details_entry_id.as_ref() == Some(&entry_clone.id) {
+                                            ui.separator();
+                                            self.show_entry_details(ctx, ui, &entry_clone);
+                                        }
                                     });
                             });
                             
@@ -170,32 +263,116 @@ impl PasswordManagerApp {
                         }
                     });
                 
-                // Rimuovi password
-                if !remove_indices.is_empty() {
-                    
-                    remove_indices.sort_by(|a, b| b.cmp(a));
+                // Apri l'editor sull'entry scelta
+                if let Some(id) = edit_id {
+                    self.start_editing(&id);
+                    self.active_tab = 1;
+                }
+
+                // Sposta le password nel cestino
+                if !remove_ids.is_empty() {
+                    let previous = self.app_data.clone();
+                    let removed_names = self.app_data.trash_entries(&remove_ids);
                     
-                    let mut removed_names = Vec::new();
-                    for &index in &remove_indices {
-                        if index < self.app_data.ps.len() {
-                            let removed_entry = self.app_data.ps.remove(index);
-                            removed_names.push(removed_entry.name);
-                            // Rimuovi anche dalle password mostrate se presente
-                            self.shown_passwords.remove(&index);
+                    if !removed_names.is_empty() && self.commit(previous) {
+                        // Rimuovi anche dalle password mostrate se presente
+                        for id in &remove_ids {
+                            self.shown_passwords.remove(id);
                         }
-                    }
-                    
-                    if !removed_names.is_empty() {
-                        save_data(&self.app_data);
+
+                        // Chiudi l'editor se stava modificando un'entry eliminata
+                        if self.edit_entry_id.as_ref().is_some_and(|id| remove_ids.contains(id)) {
+                            self.cancel_editing();
+                        }
+
                         if removed_names.len() == 1 {
-                            self.message = format!("La password di '{}' è stata eliminata!", removed_names[0]);
+                            self.message = format!("La password di '{}' è stata spostata nel cestino!", removed_names[0]);
                         } else {
-                            self.message = format!("{} password eliminate!", removed_names.len());
+                            self.message = format!("{} password spostate nel cestino!", removed_names.len());
                         }
-                        self.message_color = egui::Color32::RED;
+                        self.message_color = egui::Color32::YELLOW;
                     }
                 }
             }
         });
     }
-}
\ No newline at end of file
+}
+
+impl PasswordManagerApp {
+    // I codici a tempo vengono ricalcolati a ogni frame, il timer in update ridisegna ogni secondo
+    fn show_otp_row(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, entry: &PasswordEntry, otp: &OtpSecret) {
+        if !otp.kind.is_time_based() {
+            self.show_hotp_row(ctx, ui, entry, otp);
+            return;
+        }
+        let Some(key) = &self.encryption_key else {
+            return;
+        };
+
+        match current_code(entry, otp, key, self.app_data.vault_id(), now_timestamp()) {
+            Ok((code, remaining)) => {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("🔢 {}", group_code(&code))).monospace().strong());
+                    ui.small(format!("({}s)", remaining));
+                    if ui.small_button("📋").on_hover_text("Copia codice").clicked() {
+                        ctx.copy_text(code.clone());
+                        self.message = format!("Il codice di '{}' è stato copiato!", entry.name);
+                        self.message_color = egui::Color32::GREEN;
+                        self.mark_used(&entry.id);
+                    }
+                });
+            }
+            Err(_) => {
+                ui.colored_label(egui::Color32::RED, "⚠ Codice OTP non disponibile");
+            }
+        }
+    }
+
+    // Un codice HOTP vale finché non viene usato: resta visibile come una password mostrata
+    fn show_hotp_row(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, entry: &PasswordEntry, otp: &OtpSecret) {
+        let shown_key = format!("{}/otp", entry.id);
+        ui.horizontal(|ui| {
+            if let Some((code, start_time)) = self.shown_passwords.get(&shown_key) {
+                let remaining_time = 10 - start_time.elapsed().as_secs();
+                ui.label(egui::RichText::new(format!("🔢 {}", group_code(code))).monospace().strong());
+                ui.small(format!("({}s)", remaining_time));
+            } else {
+                ui.small(format!("🔢 HOTP • contatore {}", otp.counter));
+            }
+
+            if ui.small_button("➕").on_hover_text("Genera e copia il prossimo codice").clicked()
+                && let Some(code) = self.next_hotp_code(&entry.id)
+            {
+                ctx.copy_text(code.clone());
+                self.shown_passwords.insert(shown_key, (Zeroizing::new(code), Instant::now()));
+                self.message = format!("Nuovo codice di '{}' copiato!", entry.name);
+                self.message_color = egui::Color32::GREEN;
+            }
+        });
+    }
+}
+
+// "123456" -> "123 456", più facile da leggere e ricopiare. I codici Steam restano interi
+fn group_code(code: &str) -> String {
+    if !code.len().is_multiple_of(2) {
+        return code.to_string();
+    }
+    let (first, second) = code.split_at(code.len() / 2);
+    format!("{} {}", first, second)
+}
+
+// Riga di riepilogo per le entry che non sono login
+fn typed_summary(entry: &PasswordEntry) -> String {
+    let visible = entry
+        .kind
+        .fields()
+        .iter()
+        .filter(|field| !field.secret)
+        .find_map(|field| entry.data_field(field.key).map(|stored| stored.value.lines().next().unwrap_or_default()));
+
+    match visible {
+        Some(value) if value.chars().count() > 40 => format!("{}…", value.chars().take(40).collect::<String>()),
+        Some(value) => value.to_string(),
+        None => "🔒 Contenuto protetto".to_string(),
+    }
+}
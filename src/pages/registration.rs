@@ -32,14 +32,14 @@ impl PasswordManagerApp {
                                 ui.end_row();
                                 
                                 ui.label("🔑 Password:");
-                                ui.add(egui::TextEdit::singleline(&mut self.reg_password)
+                                ui.add(egui::TextEdit::singleline(&mut *self.reg_password)
                                     .password(!self.show_password)
                                     .desired_width(200.0));
                                 ui.checkbox(&mut self.show_password, "Mostra");
                                 ui.end_row();
                                 
                                 ui.label("🔑 Conferma:");
-                                ui.add(egui::TextEdit::singleline(&mut self.reg_confirm_password)
+                                ui.add(egui::TextEdit::singleline(&mut *self.reg_confirm_password)
                                     .password(!self.show_password1)
                                     .desired_width(200.0));
                                 ui.checkbox(&mut self.show_password1, "Mostra");
@@ -0,0 +1,283 @@
+use crate::PasswordManagerApp;
+use crate::helpers::utils::{KdfParams, benchmark_kdf, confirm_dialog};
+use super::generator::show_generator_options;
+use eframe::egui;
+
+impl PasswordManagerApp {
+    pub fn show_settings_panel(&mut self, ui: &mut egui::Ui) {
+        ui.push_id("master_password_panel", |ui| {
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(8.0)
+                .inner_margin(20.0)
+                .show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        ui.strong("🔐 Cambia Master Password");
+                        ui.add_space(15.0);
+
+                        ui.label("🔑 Password attuale");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut *self.change_old_password)
+                                .password(true)
+                                .min_size(egui::vec2(230.0, 25.0)),
+                        );
+                        ui.add_space(10.0);
+
+                        ui.label("🔑 Nuova password");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut *self.change_new_password)
+                                .password(!self.show_password)
+                                .hint_text("Almeno 6 caratteri")
+                                .min_size(egui::vec2(230.0, 25.0)),
+                        );
+                        ui.checkbox(&mut self.show_password, "Mostra");
+                        ui.add_space(10.0);
+
+                        ui.label("🔑 Conferma password");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut *self.change_confirm_password)
+                                .password(!self.show_password1)
+                                .hint_text("Ripeti la nuova password")
+                                .min_size(egui::vec2(230.0, 25.0)),
+                        );
+                        ui.checkbox(&mut self.show_password1, "Mostra");
+                        ui.add_space(15.0);
+
+                        if ui
+                            .add_sized([230.0, 35.0], egui::Button::new("🔄 Cambia Password"))
+                            .clicked()
+                        {
+                            self.show_password = false;
+                            self.show_password1 = false;
+                            self.change_master_password();
+                        }
+                    });
+                });
+        });
+
+        ui.add_space(10.0);
+
+        ui.push_id("kdf_panel", |ui| {
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(8.0)
+                .inner_margin(20.0)
+                .show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        ui.strong("🧮 Derivazione Chiave");
+                        ui.add_space(15.0);
+
+                        let defaults = KdfParams::default();
+                        let mut changed = false;
+
+                        ui.label("Algoritmo");
+                        egui::ComboBox::from_id_salt("kdf_algorithm")
+                            .selected_text(&self.kdf_draft.algorithm)
+                            .show_ui(ui, |ui| {
+                                for algorithm in KdfParams::ALGORITHMS {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut self.kdf_draft.algorithm,
+                                            algorithm.to_string(),
+                                            algorithm,
+                                        )
+                                        .changed();
+                                }
+                            });
+                        ui.add_space(10.0);
+
+                        ui.label("Memoria (MiB)");
+                        let mut memory_mib = self.kdf_draft.m_cost / 1024;
+                        if ui
+                            .add(egui::DragValue::new(&mut memory_mib).range(defaults.m_cost / 1024..=KdfParams::MAX_MEMORY_MIB))
+                            .changed()
+                        {
+                            self.kdf_draft.m_cost = memory_mib * 1024;
+                            changed = true;
+                        }
+                        ui.add_space(10.0);
+
+                        ui.label("Iterazioni");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.kdf_draft.t_cost).range(defaults.t_cost..=KdfParams::MAX_ITERATIONS))
+                            .changed();
+                        ui.add_space(10.0);
+
+                        ui.label("Parallelismo");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.kdf_draft.p_cost).range(defaults.p_cost..=KdfParams::MAX_PARALLELISM))
+                            .changed();
+                        ui.add_space(10.0);
+
+                        if changed {
+                            self.kdf_benchmark = None;
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button("⏱ Misura").on_hover_text("Calcola quanto durerà lo sblocco").clicked() {
+                                self.kdf_benchmark = Some(benchmark_kdf(&self.kdf_draft));
+                            }
+
+                            match &self.kdf_benchmark {
+                                Some(Ok(duration)) => {
+                                    ui.label(format!("Sblocco in circa {:.2} s", duration.as_secs_f32()));
+                                }
+                                Some(Err(e)) => {
+                                    ui.colored_label(egui::Color32::RED, format!("⚠ {}", e));
+                                }
+                                None => {}
+                            }
+                        });
+                        ui.add_space(10.0);
+
+                        ui.label("🔑 Password attuale");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut *self.kdf_password)
+                                .password(true)
+                                .hint_text("Necessaria per ricalcolare la chiave")
+                                .min_size(egui::vec2(230.0, 25.0)),
+                        );
+                        ui.add_space(15.0);
+
+                        if ui
+                            .add_sized([230.0, 35.0], egui::Button::new("🔄 Applica Parametri"))
+                            .clicked()
+                        {
+                            self.apply_kdf_params();
+                        }
+                    });
+                });
+        });
+
+        ui.add_space(10.0);
+
+        ui.push_id("settings_panel", |ui| {
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(8.0)
+                .inner_margin(20.0)
+                .show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        ui.strong("💾 Dati");
+                        ui.add_space(15.0);
+
+                        ui.label("Copie di sicurezza da mantenere");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings_draft.backup_count)
+                                .range(0..=20),
+                        );
+                        ui.small("I backup vengono salvati accanto a data.json");
+                        ui.add_space(10.0);
+
+                        ui.label("Giorni di conservazione nel cestino");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings_draft.trash_retention_days)
+                                .range(0..=365),
+                        );
+                        ui.small("0 = le password eliminate restano nel cestino finché non lo svuoti");
+                        ui.add_space(10.0);
+
+                        ui.label("Password precedenti da conservare");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings_draft.history_depth)
+                                .range(0..=50),
+                        );
+                        ui.small("Per ogni voce, 0 = nessuna cronologia");
+                        ui.add_space(10.0);
+
+                        ui.label("Dimensione massima allegati (MB)");
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings_draft.attachment_limit_mb)
+                                .range(1..=100),
+                        );
+                        ui.small("Gli allegati sono cifrati nella cartella attachments accanto a data.json");
+                        ui.add_space(15.0);
+
+                        if ui
+                            .add_sized([230.0, 35.0], egui::Button::new("💾 Salva Impostazioni"))
+                            .clicked()
+                        {
+                            let previous = self.app_data.clone();
+                            self.app_data.settings = self.settings_draft.clone();
+                            self.app_data.purge_expired_trash();
+                            self.app_data.trim_history();
+                            if self.commit(previous) {
+                                self.remove_unused_attachments();
+                                self.message = "Impostazioni salvate!".to_string();
+                                self.message_color = egui::Color32::GREEN;
+                            }
+                        }
+                    });
+                });
+        });
+
+        ui.add_space(10.0);
+
+        ui.push_id("generator_profiles_panel", |ui| {
+            egui::Frame::new()
+                .fill(ui.visuals().faint_bg_color)
+                .corner_radius(8.0)
+                .inner_margin(20.0)
+                .show(ui, |ui| {
+                    ui.vertical(|ui| {
+                        ui.strong("📐 Profili del generatore");
+                        ui.add_space(15.0);
+
+                        if self.app_data.profiles.is_empty() {
+                            ui.small("Nessun profilo salvato");
+                        }
+
+                        let mut edit_id: Option<String> = None;
+                        let mut delete_id: Option<String> = None;
+                        for profile in &self.app_data.profiles {
+                            ui.horizontal(|ui| {
+                                ui.label(&profile.name);
+                                ui.small(profile.options.summary());
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.small_button("🗑").on_hover_text("Elimina profilo").clicked()
+                                        && confirm_dialog(
+                                            "Conferma Eliminazione",
+                                            "Sei sicuro di voler eliminare questo profilo? Le password che lo usano torneranno alle opzioni normali.",
+                                        )
+                                    {
+                                        delete_id = Some(profile.id.clone());
+                                    }
+                                    if ui.small_button("✏").on_hover_text("Modifica profilo").clicked() {
+                                        edit_id = Some(profile.id.clone());
+                                    }
+                                });
+                            });
+                        }
+                        if let Some(id) = edit_id {
+                            self.start_profile_editing(&id);
+                        }
+                        if let Some(id) = delete_id {
+                            self.delete_generator_profile(&id);
+                        }
+                        ui.add_space(10.0);
+
+                        ui.label(if self.editing_profile_id.is_some() { "✏ Modifica profilo" } else { "➕ Nuovo profilo" });
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.profile_name_input)
+                                .hint_text("es. Banca (8-16 caratteri)")
+                                .min_size(egui::vec2(230.0, 25.0)),
+                        );
+                        show_generator_options(ui, &mut self.profile_draft, "profile_generator_options");
+                        ui.add_space(15.0);
+
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_sized([230.0, 35.0], egui::Button::new("💾 Salva Profilo"))
+                                .clicked()
+                            {
+                                self.save_generator_profile();
+                            }
+                            if self.editing_profile_id.is_some() && ui.button("❌ Annulla").clicked() {
+                                self.cancel_profile_editing();
+                            }
+                        });
+                    });
+                });
+        });
+    }
+}
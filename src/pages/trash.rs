@@ -0,0 +1,125 @@
+use crate::PasswordManagerApp;
+use crate::helpers::utils::{confirm_notification, format_age};
+use eframe::egui;
+
+impl PasswordManagerApp {
+    pub fn show_trash_list(&mut self, ui: &mut egui::Ui) {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.strong("🗑 Cestino");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("⬅ Torna alle password").clicked() {
+                        self.show_trash = false;
+                    }
+
+                    if !self.app_data.trash.is_empty()
+                        && ui.button("🔥 Svuota").on_hover_text("Elimina tutto definitivamente").clicked()
+                        && confirm_notification()
+                    {
+                        let previous = self.app_data.clone();
+                        let count = self.app_data.trash.len();
+                        self.app_data.trash.clear();
+                        if self.commit(previous) {
+                            self.remove_unused_attachments();
+                            self.message = format!("{} password eliminate definitivamente!", count);
+                            self.message_color = egui::Color32::RED;
+                        }
+                    }
+                });
+            });
+
+            let retention_days = self.app_data.settings.trash_retention_days;
+            if retention_days == 0 {
+                ui.small("Le password restano nel cestino finché non lo svuoti");
+            } else {
+                ui.small(format!("Le password vengono eliminate definitivamente dopo {} giorni", retention_days));
+            }
+
+            ui.add_space(10.0);
+
+            let remaining_space = ui.available_size();
+
+            if self.app_data.trash.is_empty() {
+                ui.allocate_ui_with_layout(
+                    remaining_space,
+                    egui::Layout::centered_and_justified(egui::Direction::TopDown),
+                    |ui| {
+                        ui.label("📭 Il cestino è vuoto");
+                    },
+                );
+                return;
+            }
+
+            let mut restore_id: Option<String> = None;
+            let mut purge_id: Option<String> = None;
+
+            // Le più recenti in alto
+            let mut trashed = self.app_data.trash.clone();
+            trashed.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted_at));
+
+            egui::ScrollArea::vertical()
+                .id_salt("trash_list_scroll")
+                .auto_shrink([false, false])
+                .min_scrolled_height(remaining_space.y)
+                .max_height(remaining_space.y)
+                .show(ui, |ui| {
+                    for trashed_entry in trashed {
+                        let entry = &trashed_entry.entry;
+                        ui.push_id(format!("trash_entry_{}", entry.id), |ui| {
+                            egui::Frame::new()
+                                .fill(ui.visuals().window_fill)
+                                .corner_radius(6.0)
+                                .inner_margin(12.0)
+                                .stroke(egui::Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color))
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.vertical(|ui| {
+                                            ui.horizontal(|ui| {
+                                                ui.strong(&entry.name);
+                                                ui.label("•");
+                                                ui.weak(&entry.u);
+                                            });
+                                            ui.small(format!("Eliminata {}", format_age(trashed_entry.deleted_at)));
+                                        });
+
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            if ui.button("❌").on_hover_text("Elimina definitivamente").clicked()
+                                                && confirm_notification()
+                                            {
+                                                purge_id = Some(entry.id.clone());
+                                            }
+
+                                            if ui.button("♻").on_hover_text("Ripristina").clicked() {
+                                                restore_id = Some(entry.id.clone());
+                                            }
+                                        });
+                                    });
+                                });
+                        });
+
+                        ui.add_space(8.0);
+                    }
+                });
+
+            if let Some(id) = restore_id {
+                let previous = self.app_data.clone();
+                if let Some(name) = self.app_data.restore_from_trash(&id)
+                    && self.commit(previous)
+                {
+                    self.message = format!("La password di '{}' è stata ripristinata!", name);
+                    self.message_color = egui::Color32::GREEN;
+                }
+            }
+
+            if let Some(id) = purge_id {
+                let previous = self.app_data.clone();
+                self.app_data.trash.retain(|trashed| trashed.entry.id != id);
+                if self.commit(previous) {
+                    self.remove_unused_attachments();
+                    self.message = "Password eliminata definitivamente!".to_string();
+                    self.message_color = egui::Color32::RED;
+                }
+            }
+        });
+    }
+}